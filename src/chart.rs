@@ -0,0 +1,227 @@
+//! Charts of the points in a list, for `export --format png|svg`. Only
+//! built with the `chart` feature, as plotters needs fontconfig and
+//! freetype to draw text.
+
+use std::path::Path;
+
+use chrono::{Local, TimeDelta};
+use clap::ValueEnum;
+use plotters::{coord::Shift, prelude::*};
+
+use crate::{PlannerError, TaskList, compute_stats};
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum ChartKind {
+    /// A pie of the completed against remaining points of the whole list
+    Points,
+    /// A bar for each tag, split into completed and remaining points
+    Tags,
+}
+
+/// One bar of the chart: its label, the points earned and the points left.
+pub type Bar = (String, u64, u64);
+
+pub const CHART_SIZE: (u32, u32) = (800, 500);
+
+const DONE_COLOR: RGBColor = RGBColor(76, 175, 80);
+const LEFT_COLOR: RGBColor = RGBColor(189, 189, 189);
+
+/// Adds up the points to show for `kind`.
+pub fn chart_bars(tasklist: &TaskList, kind: ChartKind) -> Vec<Bar> {
+    let stats = compute_stats(
+        tasklist,
+        Local::now(),
+        TimeDelta::zero(),
+        kind == ChartKind::Tags,
+    );
+
+    // Streak bonuses are left out so the bars never go past the total
+    if kind == ChartKind::Points {
+        let earned = stats.points_earned - stats.points_bonus;
+
+        return vec![("All tasks".to_string(), earned, stats.points_total - earned)];
+    }
+
+    let mut bars: Vec<Bar> = vec![];
+
+    for (tag, x) in stats.by_tag.unwrap() {
+        let earned = x.earned - x.bonus;
+
+        bars.push((tag, earned, x.total - earned));
+    }
+
+    bars
+}
+
+fn draw_bars<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    bars: &[Bar],
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    root.fill(&WHITE)?;
+
+    let top = bars.iter().map(|x| x.1 + x.2).max().unwrap_or(0).max(1);
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption("Completed and remaining points", ("sans-serif", 24))
+        .margin(20)
+        .x_label_area_size(40)
+        .y_label_area_size(50)
+        .build_cartesian_2d((0..bars.len() - 1).into_segmented(), 0..top)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_labels(bars.len())
+        .x_label_formatter(&|x| match x {
+            SegmentValue::CenterOf(i) => bars.get(*i).map(|b| b.0.clone()).unwrap_or_default(),
+            _ => String::new(),
+        })
+        .y_desc("Points")
+        .draw()?;
+
+    let bar = |i: usize, from: u64, to: u64, color: RGBColor| {
+        // A single bar spans a range with no width, so end the last one at
+        // the edge instead
+        let right = if i + 1 == bars.len() {
+            SegmentValue::Last
+        } else {
+            SegmentValue::Exact(i + 1)
+        };
+
+        let mut rect = Rectangle::new(
+            [(SegmentValue::Exact(i), from), (right, to)],
+            color.filled(),
+        );
+        rect.set_margin(0, 0, 10, 10);
+
+        rect
+    };
+
+    chart
+        .draw_series(
+            bars.iter()
+                .enumerate()
+                .map(|(i, x)| bar(i, 0, x.1, DONE_COLOR)),
+        )?
+        .label("Completed")
+        .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], DONE_COLOR.filled()));
+
+    chart
+        .draw_series(
+            bars.iter()
+                .enumerate()
+                .map(|(i, x)| bar(i, x.1, x.1 + x.2, LEFT_COLOR)),
+        )?
+        .label("Remaining")
+        .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], LEFT_COLOR.filled()));
+
+    chart
+        .configure_series_labels()
+        .background_style(WHITE)
+        .border_style(BLACK)
+        .draw()?;
+
+    root.present()?;
+
+    Ok(())
+}
+
+fn draw_pie<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    earned: u64,
+    left: u64,
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    root.fill(&WHITE)?;
+
+    let root = root.titled("Completed and remaining points", ("sans-serif", 24))?;
+
+    let mut sizes: Vec<f64> = vec![];
+    let mut colors: Vec<RGBColor> = vec![];
+    let mut labels: Vec<String> = vec![];
+
+    // Empty slices would still get a label
+    if earned > 0 {
+        sizes.push(earned as f64);
+        colors.push(DONE_COLOR);
+        labels.push(format!("Completed ({earned})"));
+    }
+
+    if left > 0 {
+        sizes.push(left as f64);
+        colors.push(LEFT_COLOR);
+        labels.push(format!("Remaining ({left})"));
+    }
+
+    let (width, height) = root.dim_in_pixel();
+    let center = (width as i32 / 2, height as i32 / 2);
+    let radius = width.min(height) as f64 * 0.35;
+
+    let mut pie = Pie::new(&center, &radius, &sizes, &colors, &labels);
+    pie.start_angle(-90.0);
+    pie.label_style(("sans-serif", 16).into_font());
+    pie.percentages(("sans-serif", 14).into_font());
+
+    root.draw(&pie)?;
+    root.present()?;
+
+    Ok(())
+}
+
+fn draw_chart<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    tasklist: &TaskList,
+    kind: ChartKind,
+) -> Result<(), PlannerError> {
+    if tasklist.tasks.is_empty() {
+        return Err(PlannerError::Generic(
+            "The list has no tasks to chart".to_string(),
+        ));
+    }
+
+    let bars = chart_bars(tasklist, kind);
+
+    let result = match kind {
+        ChartKind::Points => {
+            if bars[0].1 + bars[0].2 == 0 {
+                return Err(PlannerError::Generic(
+                    "The tasks have no points to chart".to_string(),
+                ));
+            }
+
+            draw_pie(root, bars[0].1, bars[0].2)
+        }
+        ChartKind::Tags => draw_bars(root, &bars),
+    };
+
+    if let Err(e) = result {
+        return Err(PlannerError::Generic(format!(
+            "Could not draw the chart: {e}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Draws the chart as a PNG image at `path`.
+pub fn write_png_chart(
+    tasklist: &TaskList,
+    kind: ChartKind,
+    path: &Path,
+) -> Result<(), PlannerError> {
+    let root = BitMapBackend::new(path, CHART_SIZE).into_drawing_area();
+
+    draw_chart(root, tasklist, kind)
+}
+
+/// Draws the chart as an SVG document.
+pub fn generate_svg_chart(tasklist: &TaskList, kind: ChartKind) -> Result<String, PlannerError> {
+    let mut svg = String::new();
+
+    draw_chart(
+        SVGBackend::with_string(&mut svg, CHART_SIZE).into_drawing_area(),
+        tasklist,
+        kind,
+    )?;
+
+    Ok(svg)
+}
@@ -0,0 +1,4372 @@
+//! The task lists, their storage and the operations on them, shared by the
+//! `planner` command line tool and any program that wants to embed it.
+
+#[cfg(feature = "chart")]
+pub mod chart;
+#[cfg(test)]
+mod tests;
+
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    env::current_dir,
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use chrono::{
+    DateTime, Datelike, Days, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc,
+    Weekday,
+    format::{Item, StrftimeItems},
+};
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use uuid::Uuid;
+
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d at %H:%M:%S";
+
+/// Version of the task list file layout, stored in every list written.
+/// Lists from before it was stored have no version.
+///
+/// - 2: tasks have a `uuid`
+pub const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorScheme {
+    /// Green, yellow and red
+    #[default]
+    Default,
+    /// Bold blue, yellow and magenta, distinguishable without red/green
+    HighContrast,
+    /// No colors, a symbol in front of the text instead
+    Mono,
+    /// No highlighting at all
+    Off,
+}
+
+/// How good a highlighted piece of output is, rendered by `colorize`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    Good,
+    Warning,
+    Bad,
+}
+
+/// The ANSI code for a color name like "red", or "bold-red" for the bold
+/// variant. `None` for names it doesn't know.
+pub fn color_code(name: &str) -> Option<String> {
+    let (bold, base) = match name.trim().to_lowercase().strip_prefix("bold-") {
+        Some(x) => (true, x.to_string()),
+        None => (false, name.trim().to_lowercase()),
+    };
+
+    let code = match base.as_str() {
+        "black" => "30",
+        "red" => "31",
+        "green" => "32",
+        "yellow" => "33",
+        "blue" => "34",
+        "magenta" => "35",
+        "cyan" => "36",
+        "white" => "37",
+        _ => return None,
+    };
+
+    if bold {
+        return Some(format!("1;{code}"));
+    }
+
+    Some(code.to_string())
+}
+
+pub fn paint(text: &str, code: &str) -> String {
+    format!("\x1b[{code}m{text}\x1b[0m")
+}
+
+/// The single place deciding how a status looks under each color scheme.
+pub fn colorize(text: &str, status: Status, scheme: ColorScheme) -> String {
+    let name = match (scheme, status) {
+        (ColorScheme::Default, Status::Good) => "green",
+        (ColorScheme::Default, Status::Warning) => "yellow",
+        (ColorScheme::Default, Status::Bad) => "red",
+        (ColorScheme::HighContrast, Status::Good) => "bold-blue",
+        (ColorScheme::HighContrast, Status::Warning) => "bold-yellow",
+        (ColorScheme::HighContrast, Status::Bad) => "bold-magenta",
+        (ColorScheme::Off, _) => return text.to_string(),
+        (ColorScheme::Mono, _) => {
+            let symbol = match status {
+                Status::Good => "✓",
+                Status::Warning => "○",
+                Status::Bad => "!",
+            };
+
+            return format!("{symbol} {text}");
+        }
+    };
+
+    paint(text, &color_code(name).unwrap())
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum TaskField {
+    Id,
+    Name,
+    Points,
+    Due,
+    Complete,
+    Tags,
+    Priority,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum CountBy {
+    /// The local day the task is due on
+    DueDay,
+    Tag,
+    Priority,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SortKey {
+    Id,
+    Name,
+    /// Most points first
+    Points,
+    /// Highest priority first
+    Priority,
+    /// Soonest due first, tasks without a due date last
+    Due,
+    /// Most urgent first
+    Urgency,
+    /// Open tasks soonest due first, then completed tasks in the order they
+    /// were completed
+    Smart,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum MergePolicy {
+    /// Leave the existing task and drop the imported one
+    Skip,
+    /// Overwrite the existing task with the imported one, keeping its id and links
+    Replace,
+    /// Add the imported task anyway under a new id
+    Append,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+pub struct TaskList {
+    /// The `SCHEMA_VERSION` the list was last written with
+    #[serde(default)]
+    pub version: Option<u32>,
+    pub tasks: Vec<Task>,
+}
+
+// Derived through `remote = "Self"` so the `Deserialize` impl below can fix
+// up what the derive reads
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(remote = "Self")]
+pub struct Task {
+    pub name: String,
+    pub points: u32,
+    pub id: usize,
+    pub complete: bool,
+    #[serde(default, with = "utc_time_opt")]
+    #[schemars(with = "Option<DateTime<Utc>>")]
+    pub due_date: Option<DateTime<Local>>,
+    #[serde(default, with = "utc_time_opt")]
+    #[schemars(with = "Option<DateTime<Utc>>")]
+    pub start_time: Option<DateTime<Local>>,
+    pub parent: Option<usize>,
+    pub resources: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub depends_on: Vec<usize>,
+    #[serde(default, with = "utc_time_opt")]
+    #[schemars(with = "Option<DateTime<Utc>>")]
+    pub completed_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub completion_note: Option<String>,
+    #[serde(default, with = "utc_time_opt")]
+    #[schemars(with = "Option<DateTime<Utc>>")]
+    pub created_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    #[serde(default, with = "duration_seconds")]
+    #[schemars(with = "Vec<i64>")]
+    pub reminders: Vec<TimeDelta>,
+    /// Percentage of the task done, `None` when it is only tracked as done
+    /// or not. Reaching 100 checks the task off.
+    #[serde(default)]
+    pub progress: Option<u8>,
+    #[serde(default)]
+    pub priority: u8,
+    /// When a task checked off with `check --undo-after` opens again
+    #[serde(default, with = "utc_time_opt")]
+    #[schemars(with = "Option<DateTime<Utc>>")]
+    pub reopen_at: Option<DateTime<Local>>,
+    /// Extra points awarded for completing it during a streak
+    #[serde(default)]
+    pub bonus_points: u32,
+    /// Points taken off for completing it after its due date
+    #[serde(default)]
+    pub penalty_points: u32,
+    /// Identifies the task across copies of the list, so merging them can
+    /// tell which tasks are the same
+    #[serde(default)]
+    pub uuid: Option<String>,
+}
+
+impl Serialize for Task {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        Task::serialize(self, s)
+    }
+}
+
+impl<'de> Deserialize<'de> for Task {
+    /// Reads a task, treating one at 100% progress as complete whatever its
+    /// `complete` field says.
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Task, D::Error> {
+        let mut task = Task::deserialize(d)?;
+
+        if task.progress == Some(100) {
+            task.complete = true;
+        }
+
+        Ok(task)
+    }
+}
+
+/// Stores a list of durations as whole seconds.
+mod duration_seconds {
+    use chrono::TimeDelta;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(durations: &[TimeDelta], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(durations.iter().map(|x| x.num_seconds()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<TimeDelta>, D::Error> {
+        let seconds: Vec<i64> = Vec::deserialize(d)?;
+
+        Ok(seconds.into_iter().map(TimeDelta::seconds).collect())
+    }
+}
+
+/// Stores a timestamp as RFC3339 in UTC, so a list synced between machines
+/// in different time zones reads the same everywhere. Any offset is accepted
+/// when reading, and so is a timestamp without one, taken as local time.
+mod utc_time {
+    use chrono::{DateTime, Local, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(time: &DateTime<Local>, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(
+            &time
+                .with_timezone(&Utc)
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        )
+    }
+
+    pub fn parse(raw: &str) -> Option<DateTime<Local>> {
+        if let Ok(x) = DateTime::parse_from_rfc3339(raw) {
+            return Some(x.with_timezone(&Local));
+        }
+
+        let naive = NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+
+        Local.from_local_datetime(&naive).earliest()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<DateTime<Local>, D::Error> {
+        let raw = String::deserialize(d)?;
+
+        parse(&raw).ok_or_else(|| D::Error::custom(format!("invalid timestamp '{raw}'")))
+    }
+}
+
+/// `utc_time` for optional timestamps.
+mod utc_time_opt {
+    use chrono::{DateTime, Local};
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(
+        time: &Option<DateTime<Local>>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(x) => super::utc_time::serialize(x, s),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<Option<DateTime<Local>>, D::Error> {
+        let Some(raw) = Option::<String>::deserialize(d)? else {
+            return Ok(None);
+        };
+
+        match super::utc_time::parse(&raw) {
+            Some(x) => Ok(Some(x)),
+            None => Err(D::Error::custom(format!("invalid timestamp '{raw}'"))),
+        }
+    }
+}
+
+/// How a task repeats. When a recurring task is checked off a copy is
+/// created with its dates moved forward by `every_seconds`, unless that
+/// would go past `until` or `remaining` says this is the last occurrence.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct Recurrence {
+    pub every_seconds: i64,
+    #[serde(default, with = "utc_time_opt")]
+    #[schemars(with = "Option<DateTime<Utc>>")]
+    pub until: Option<DateTime<Local>>,
+    /// Occurrences left including this one, `None` for no limit
+    #[serde(default)]
+    pub remaining: Option<u32>,
+    /// The id of the next occurrence once checking this one off created it
+    #[serde(default)]
+    pub next_id: Option<usize>,
+}
+
+impl Recurrence {
+    /// The time between occurrences, failing for a stored `every_seconds`
+    /// too large to be a duration.
+    pub fn every(&self) -> Result<TimeDelta, PlannerError> {
+        TimeDelta::try_seconds(self.every_seconds).ok_or_else(|| {
+            PlannerError::Parse(format!(
+                "Invalid recurrence of every {} seconds",
+                self.every_seconds
+            ))
+        })
+    }
+}
+
+impl Task {
+    /// Whether the task is open and its due date has passed. A task due
+    /// exactly at `now` isn't overdue yet.
+    pub fn is_overdue(&self, now: DateTime<Local>) -> bool {
+        self.is_overdue_after(now, TimeDelta::zero())
+    }
+
+    /// Like `is_overdue`, but only once the due date is more than `grace`
+    /// in the past.
+    pub fn is_overdue_after(&self, now: DateTime<Local>, grace: TimeDelta) -> bool {
+        !self.complete && self.due_date.is_some_and(|x| x + grace < now)
+    }
+
+    /// How long until the task is due, negative once that has passed.
+    pub fn time_left(&self, now: DateTime<Local>) -> Option<TimeDelta> {
+        self.due_date.map(|x| x - now)
+    }
+
+    /// Whether the task is open and due before `now + window`, including
+    /// tasks that are already overdue.
+    pub fn is_due_within(&self, now: DateTime<Local>, window: TimeDelta) -> bool {
+        !self.complete && self.time_left(now).is_some_and(|x| x < window)
+    }
+
+    pub fn new(id: usize, name: String, points: u32) -> Task {
+        Task {
+            name,
+            points,
+            id,
+            complete: false,
+            due_date: None,
+            start_time: None,
+            parent: None,
+            resources: vec![],
+            tags: vec![],
+            depends_on: vec![],
+            completed_at: None,
+            completion_note: None,
+            created_at: Some(Local::now()),
+            recurrence: None,
+            reminders: vec![],
+            progress: None,
+            priority: 0,
+            reopen_at: None,
+            bonus_points: 0,
+            penalty_points: 0,
+            uuid: Some(Uuid::new_v4().to_string()),
+        }
+    }
+
+    /// Opens the task again, clearing its progress and everything recorded
+    /// when it was checked off.
+    pub fn reset_completion(&mut self) {
+        self.complete = false;
+        self.completed_at = None;
+        self.completion_note = None;
+        self.reopen_at = None;
+        self.bonus_points = 0;
+        self.penalty_points = 0;
+        self.progress = None;
+    }
+
+    /// The points of the task including any streak bonus and late penalty.
+    pub fn total_points(&self) -> u64 {
+        (self.points as u64 + self.bonus_points as u64).saturating_sub(self.penalty_points as u64)
+    }
+
+    /// The part of `total_points` that came from the streak bonus, which is
+    /// what is left of it once a late penalty bigger than `points` is taken.
+    pub fn earned_bonus(&self) -> u64 {
+        self.total_points() - (self.points as u64).saturating_sub(self.penalty_points as u64)
+    }
+
+    /// Starts building an open task, see `TaskBuilder`.
+    pub fn builder(id: usize, name: String, points: u32) -> TaskBuilder {
+        TaskBuilder {
+            task: Task::new(id, name, points),
+        }
+    }
+}
+
+/// Sets the optional fields of a new task one at a time, starting from
+/// `Task::builder` and finishing with `build`.
+pub struct TaskBuilder {
+    task: Task,
+}
+
+impl TaskBuilder {
+    pub fn due_date(mut self, due_date: Option<DateTime<Local>>) -> TaskBuilder {
+        self.task.due_date = due_date;
+        self
+    }
+
+    pub fn start_time(mut self, start_time: Option<DateTime<Local>>) -> TaskBuilder {
+        self.task.start_time = start_time;
+        self
+    }
+
+    pub fn parent(mut self, parent: Option<usize>) -> TaskBuilder {
+        self.task.parent = parent;
+        self
+    }
+
+    pub fn resources(mut self, resources: Vec<String>) -> TaskBuilder {
+        self.task.resources = resources;
+        self
+    }
+
+    pub fn tags(mut self, tags: Vec<String>) -> TaskBuilder {
+        self.task.tags = tags;
+        self
+    }
+
+    pub fn depends_on(mut self, depends_on: Vec<usize>) -> TaskBuilder {
+        self.task.depends_on = depends_on;
+        self
+    }
+
+    pub fn priority(mut self, priority: u8) -> TaskBuilder {
+        self.task.priority = priority;
+        self
+    }
+
+    pub fn recurrence(mut self, recurrence: Option<Recurrence>) -> TaskBuilder {
+        self.task.recurrence = recurrence;
+        self
+    }
+
+    pub fn reminders(mut self, reminders: Vec<TimeDelta>) -> TaskBuilder {
+        self.task.reminders = reminders;
+        self
+    }
+
+    pub fn build(self) -> Task {
+        self.task
+    }
+}
+
+/// Every way a command can fail. Each variant maps to a stable exit code so
+/// scripts can tell failures apart, as listed in `planner --help`.
+#[derive(Debug)]
+pub enum PlannerError {
+    Generic(String),
+    NotInitialized,
+    TaskNotFound(usize),
+    Parse(String),
+}
+
+impl PlannerError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PlannerError::Generic(_) => 1,
+            PlannerError::NotInitialized => 2,
+            PlannerError::TaskNotFound(_) => 3,
+            PlannerError::Parse(_) => 4,
+        }
+    }
+
+    /// A name for the kind of error that stays the same across versions,
+    /// unlike the message.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            PlannerError::Generic(_) => "error",
+            PlannerError::NotInitialized => "not_initialized",
+            PlannerError::TaskNotFound(_) => "task_not_found",
+            PlannerError::Parse(_) => "parse_error",
+        }
+    }
+
+    /// The error as `{"error": <code>, "message": <text>}`, with the `id`
+    /// of the task that wasn't found.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut value = serde_json::json!({
+            "error": self.error_code(),
+            "message": self.to_string(),
+        });
+
+        if let PlannerError::TaskNotFound(id) = self {
+            value["id"] = serde_json::json!(id);
+        }
+
+        value
+    }
+}
+
+impl fmt::Display for PlannerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlannerError::Generic(msg) => write!(f, "{msg}"),
+            PlannerError::NotInitialized => write!(
+                f,
+                "Meta file does not exist, use 'planner init' to create it"
+            ),
+            PlannerError::TaskNotFound(id) => write!(f, "Task #{id} not found"),
+            PlannerError::Parse(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+/// Optional settings read from `planner.config.json` next to the task file.
+/// Every key may be omitted.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Config {
+    #[serde(default)]
+    pub points_budget: Option<u32>,
+    #[serde(default)]
+    pub color_scheme: Option<ColorScheme>,
+    #[serde(default)]
+    pub date_display: DateDisplay,
+    /// How points are written on `add` and `edit`
+    #[serde(default)]
+    pub points_scheme: PointsScheme,
+    #[serde(default)]
+    pub max_points: Option<u32>,
+    #[serde(default)]
+    pub week_start: WeekStart,
+    #[serde(default)]
+    pub audit_log: bool,
+    #[serde(default)]
+    pub no_color: Option<bool>,
+    #[serde(default)]
+    pub default_list: Option<String>,
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub date_format: Option<String>,
+    /// When set, `list` archives tasks completed longer ago than this
+    #[serde(default)]
+    pub auto_archive_after: Option<String>,
+    /// Check off parents and dependents once everything they wait on is done
+    #[serde(default)]
+    pub cascade_complete: bool,
+    /// Color names for tags, like `{"work": "blue"}`
+    #[serde(default)]
+    pub tag_colors: BTreeMap<String, String>,
+    /// Color a whole open task in the color of its first colored tag
+    #[serde(default)]
+    pub color_tagged_lines: bool,
+    /// Commit the list file to git after every change, set by `init --git`
+    #[serde(default)]
+    pub git_autocommit: bool,
+    /// Flags of `list` saved under a name by `view save`
+    #[serde(default)]
+    pub views: BTreeMap<String, Vec<String>>,
+    /// Completion percentages below this are shown as bad
+    #[serde(default = "default_summary_warn_below")]
+    pub summary_warn_below: u32,
+    /// Completion percentages above this are shown as good
+    #[serde(default = "default_summary_good_above")]
+    pub summary_good_above: u32,
+    /// How long past its due date a task has to be to count as overdue
+    #[serde(default)]
+    pub overdue_threshold: Option<String>,
+    /// Award a bonus each time the days in a row with a completed task reach
+    /// another multiple of this, 0 for no streak bonus
+    #[serde(default)]
+    pub streak_bonus_days: u32,
+    /// How many points each streak bonus is worth
+    #[serde(default = "default_streak_bonus_points")]
+    pub streak_bonus_points: u32,
+    /// Percentage of its points a task loses when completed after its due
+    /// date, 0 for no penalty
+    #[serde(default)]
+    pub late_penalty_percent: u32,
+    /// The points `goal` aims to earn each period
+    #[serde(default)]
+    pub points_goal: Option<u32>,
+    /// How many snapshots of each list to keep, 0 to take none
+    #[serde(default = "default_snapshot_count")]
+    pub snapshot_count: usize,
+}
+
+pub fn default_summary_warn_below() -> u32 {
+    1
+}
+
+pub fn default_summary_good_above() -> u32 {
+    99
+}
+
+pub fn default_streak_bonus_points() -> u32 {
+    1
+}
+
+pub fn default_snapshot_count() -> usize {
+    10
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+impl WeekStart {
+    pub fn weekday(&self) -> Weekday {
+        match self {
+            WeekStart::Monday => Weekday::Mon,
+            WeekStart::Sunday => Weekday::Sun,
+        }
+    }
+}
+
+/// How points are written when adding or editing tasks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PointsScheme {
+    /// Any whole number
+    #[default]
+    Plain,
+    /// Numbers are snapped to the nearest Fibonacci number
+    Fibonacci,
+    /// Sizes from XS to XXL
+    Tshirt,
+}
+
+pub const TSHIRT_SIZES: [(&str, u32); 6] = [
+    ("XS", 1),
+    ("S", 2),
+    ("M", 3),
+    ("L", 5),
+    ("XL", 8),
+    ("XXL", 13),
+];
+
+/// The Fibonacci number closest to `value`, rounding ties up.
+pub fn nearest_fibonacci(value: u32) -> u32 {
+    if value <= 1 {
+        return value;
+    }
+
+    let (mut a, mut b) = (1u32, 2u32);
+
+    while b < value {
+        (a, b) = (b, a.saturating_add(b));
+    }
+
+    if value - a < b - value {
+        return a;
+    }
+
+    b
+}
+
+/// Reads points written in `scheme`, e.g. "M" under `Tshirt` or "4" snapped
+/// to 5 under `Fibonacci`.
+pub fn parse_points(raw: &str, scheme: PointsScheme) -> Result<u32, PlannerError> {
+    let raw = raw.trim();
+
+    if scheme == PointsScheme::Tshirt {
+        for (size, points) in TSHIRT_SIZES {
+            if raw.eq_ignore_ascii_case(size) {
+                return Ok(points);
+            }
+        }
+
+        let allowed: Vec<&str> = TSHIRT_SIZES.iter().map(|x| x.0).collect();
+
+        return Err(PlannerError::Parse(format!(
+            "Invalid size '{raw}', expected one of {}",
+            allowed.join(", ")
+        )));
+    }
+
+    let Ok(points) = raw.parse::<u32>() else {
+        return Err(PlannerError::Parse(format!(
+            "Invalid points '{raw}', expected a whole number"
+        )));
+    };
+
+    Ok(snap_points(points, scheme))
+}
+
+/// Moves a number of points onto the closest value `scheme` allows.
+pub fn snap_points(points: u32, scheme: PointsScheme) -> u32 {
+    match scheme {
+        PointsScheme::Plain => points,
+        PointsScheme::Fibonacci => nearest_fibonacci(points),
+        PointsScheme::Tshirt => {
+            let mut best = TSHIRT_SIZES[0].1;
+
+            for (_, x) in TSHIRT_SIZES {
+                if x.abs_diff(points) < best.abs_diff(points) {
+                    best = x;
+                }
+            }
+
+            best
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DateDisplay {
+    #[default]
+    Absolute,
+    Relative,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        // Same as an empty config file
+        serde_json::from_str("{}").unwrap()
+    }
+}
+
+/// Picks the value of a setting, in order of precedence: the command line
+/// flag, then the environment variable `env`, then the config file.
+pub fn resolve_setting(flag: Option<String>, env: &str, config: Option<String>) -> Option<String> {
+    if flag.is_some() {
+        return flag;
+    }
+
+    if let Ok(x) = std::env::var(env)
+        && !x.is_empty()
+    {
+        return Some(x);
+    }
+
+    config
+}
+
+pub fn get_config_path() -> PathBuf {
+    let cwd = current_dir().unwrap();
+    let mut config_path = cwd.clone();
+    config_path.push("planner.config.json");
+
+    config_path
+}
+
+/// Loads the config file, or the defaults when there is none.
+pub fn read_config() -> Result<Config, PlannerError> {
+    let config_path = get_config_path();
+
+    if !config_path.exists() {
+        return Ok(Config::default());
+    }
+
+    let raw_file = match fs::read_to_string(&config_path) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(PlannerError::Generic(format!(
+                "Could not read the config file: {e}"
+            )));
+        }
+    };
+
+    match serde_json::from_str(raw_file.as_str()) {
+        Ok(config) => Ok(config),
+        Err(e) => Err(PlannerError::Parse(format!("Invalid config file: {e}"))),
+    }
+}
+
+/// Sets a single key in the config file, creating the file if needed and
+/// leaving every other key as it was.
+pub fn set_config_value(key: &str, value: serde_json::Value) -> io::Result<()> {
+    let config_path = get_config_path();
+
+    let mut raw = serde_json::Map::new();
+
+    if let Ok(x) = fs::read_to_string(&config_path) {
+        match serde_json::from_str(&x) {
+            Ok(x) => raw = x,
+            Err(e) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid config file: {e}"),
+                ));
+            }
+        }
+    }
+
+    raw.insert(key.to_string(), value);
+
+    fs::write(&config_path, serde_json::to_string_pretty(&raw).unwrap())
+}
+
+pub fn get_meta_path(list: &str) -> PathBuf {
+    let cwd = current_dir().unwrap();
+    let mut meta_path = cwd.clone();
+    meta_path.push(list);
+    meta_path.set_extension("json");
+
+    meta_path
+}
+
+/// The JSON Schema of a list file, for editors to validate it with.
+pub fn task_list_schema() -> String {
+    serde_json::to_string_pretty(&schemars::schema_for!(TaskList)).unwrap()
+}
+
+/// Loads the task list stored at `meta_path`.
+pub fn read_task_list(meta_path: &Path) -> Result<TaskList, PlannerError> {
+    if !meta_path.exists() {
+        return Err(PlannerError::NotInitialized);
+    }
+
+    let raw_file = match fs::read_to_string(meta_path) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(PlannerError::Generic(format!(
+                "Could not read the list: {e}"
+            )));
+        }
+    };
+
+    let mut task_list: TaskList = match serde_json::from_str(raw_file.as_str()) {
+        Ok(x) => x,
+        Err(e) => return Err(PlannerError::Parse(format!("Invalid meta file: {e}"))),
+    };
+
+    migrate_task_list(&mut task_list)?;
+
+    Ok(task_list)
+}
+
+/// Brings a list read from a file up to `SCHEMA_VERSION`, failing for lists
+/// written by a newer planner. The changes are kept once the list is saved.
+pub fn migrate_task_list(tasklist: &mut TaskList) -> Result<(), PlannerError> {
+    let version = tasklist.version.unwrap_or(0);
+
+    if version > SCHEMA_VERSION {
+        return Err(PlannerError::Parse(format!(
+            "The list has schema version {version}, this planner only reads up to {SCHEMA_VERSION}"
+        )));
+    }
+
+    if version < 2 {
+        for task in tasklist.tasks.iter_mut() {
+            if task.uuid.is_none() {
+                task.uuid = Some(Uuid::new_v4().to_string());
+            }
+        }
+    }
+
+    tasklist.version = Some(SCHEMA_VERSION);
+
+    Ok(())
+}
+
+/// Lists the keys present in `raw` but not in `known`, which is the same
+/// document after a round trip through the structs. Those are the fields
+/// serde silently ignored while loading.
+pub fn find_unknown_fields(
+    raw: &serde_json::Value,
+    known: &serde_json::Value,
+    path: &str,
+) -> Vec<String> {
+    let mut unknown: Vec<String> = vec![];
+
+    match (raw, known) {
+        (serde_json::Value::Object(raw), serde_json::Value::Object(known)) => {
+            for (key, value) in raw.iter() {
+                let field = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+
+                match known.get(key) {
+                    Some(x) => unknown.append(&mut find_unknown_fields(value, x, &field)),
+                    None => unknown.push(field),
+                }
+            }
+        }
+        (serde_json::Value::Array(raw), serde_json::Value::Array(known)) => {
+            for (i, (value, x)) in raw.iter().zip(known.iter()).enumerate() {
+                unknown.append(&mut find_unknown_fields(value, x, &format!("{path}[{i}]")));
+            }
+        }
+        _ => {}
+    }
+
+    unknown
+}
+
+/// Fails when `path` contains fields `T` doesn't know, for `--strict`.
+/// A missing file passes.
+pub fn check_strict<T: Serialize + for<'a> Deserialize<'a>>(
+    path: &Path,
+) -> Result<(), PlannerError> {
+    let Ok(raw_file) = fs::read_to_string(path) else {
+        return Ok(());
+    };
+
+    let name = path.file_name().unwrap().to_string_lossy();
+
+    let (Ok(raw), Ok(parsed)) = (
+        serde_json::from_str::<serde_json::Value>(&raw_file),
+        serde_json::from_str::<T>(&raw_file),
+    ) else {
+        return Err(PlannerError::Parse(format!("Invalid file '{name}'")));
+    };
+
+    let known = serde_json::to_value(&parsed).unwrap();
+    let unknown = find_unknown_fields(&raw, &known, "");
+
+    if let Some(field) = unknown.first() {
+        return Err(PlannerError::Parse(format!(
+            "Unknown field '{field}' in '{name}'"
+        )));
+    }
+
+    Ok(())
+}
+
+/// One change to a task list, as appended to the audit log.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LogEntry {
+    #[serde(with = "utc_time")]
+    pub at: DateTime<Local>,
+    #[serde(flatten)]
+    pub action: LogAction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum LogAction {
+    Add { task: Task },
+    Update { task: Task },
+    Remove { id: usize },
+}
+
+/// Compares two versions of a task list and describes what changed,
+/// task by task.
+pub fn diff_task_lists(old: &TaskList, new: &TaskList) -> Vec<LogAction> {
+    let mut actions: Vec<LogAction> = vec![];
+
+    for task in old.tasks.iter() {
+        if get_task_index(new, task.id).is_none() {
+            actions.push(LogAction::Remove { id: task.id });
+        }
+    }
+
+    for task in new.tasks.iter() {
+        match get_task_index(old, task.id) {
+            None => actions.push(LogAction::Add { task: task.clone() }),
+            Some(idx) => {
+                let before = serde_json::to_value(&old.tasks[idx]).unwrap();
+                let after = serde_json::to_value(task).unwrap();
+
+                if before != after {
+                    actions.push(LogAction::Update { task: task.clone() });
+                }
+            }
+        }
+    }
+
+    actions
+}
+
+/// Appends one line per changed task to `<list>.log`, when `audit_log` is
+/// enabled in the config.
+pub fn append_to_audit_log(
+    meta_path: &Path,
+    old: &TaskList,
+    new: &TaskList,
+    config: &Config,
+) -> io::Result<()> {
+    if !config.audit_log {
+        return Ok(());
+    }
+
+    let now = Local::now();
+    let mut lines = String::new();
+
+    for action in diff_task_lists(old, new) {
+        let entry = LogEntry { at: now, action };
+
+        lines += serde_json::to_string(&entry).unwrap().as_str();
+        lines += "\n";
+    }
+
+    let mut log = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(meta_path.with_extension("log"))?;
+
+    log.write_all(lines.as_bytes())
+}
+
+/// Describes a change to the list in a line, like "Add task 'Report'".
+pub fn describe_changes(old: &TaskList, actions: &[LogAction]) -> String {
+    if actions.len() != 1 {
+        return format!("Update planner ({} changes)", actions.len());
+    }
+
+    match &actions[0] {
+        LogAction::Add { task } => format!("Add task '{}'", task.name),
+        LogAction::Update { task } => format!("Update task '{}'", task.name),
+        LogAction::Remove { id } => match get_task_index(old, *id) {
+            Some(x) => format!("Remove task '{}'", old.tasks[x].name),
+            None => format!("Remove task #{id}"),
+        },
+    }
+}
+
+/// Commits the list file with a message describing the change, when
+/// `git_autocommit` is enabled in the config. Fails with the reason when git
+/// is missing or the directory isn't a repository.
+pub fn git_commit_task_list(
+    meta_path: &Path,
+    old: &TaskList,
+    new: &TaskList,
+    config: &Config,
+) -> Result<(), String> {
+    if !config.git_autocommit {
+        return Ok(());
+    }
+
+    let actions = diff_task_lists(old, new);
+
+    if actions.is_empty() {
+        return Ok(());
+    }
+
+    git_commit_file(meta_path, &describe_changes(old, &actions))
+}
+
+/// Commits the list file to git with `message`, whatever the config says.
+pub fn git_commit_file(meta_path: &Path, message: &str) -> Result<(), String> {
+    let dir = match meta_path.parent() {
+        Some(x) if x.as_os_str() != "" => x.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let add = Command::new("git")
+        .arg("-C")
+        .arg(&dir)
+        .arg("add")
+        .arg(meta_path)
+        .output();
+
+    let commit = match add {
+        Ok(x) if x.status.success() => Command::new("git")
+            .arg("-C")
+            .arg(&dir)
+            .args(["commit", "-q", "-m", message, "--"])
+            .arg(meta_path)
+            .output(),
+        x => x,
+    };
+
+    match commit {
+        Ok(x) if x.status.success() => Ok(()),
+        Ok(x) => Err(format!(
+            "could not commit to git: {}",
+            String::from_utf8_lossy(&x.stderr).trim()
+        )),
+        Err(e) => Err(format!("could not run git: {e}")),
+    }
+}
+
+/// What planner remembers about a list between runs, currently where
+/// `list --page` left off.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ListState {
+    #[serde(default = "default_page")]
+    pub page: usize,
+    #[serde(default = "default_page_size")]
+    pub page_size: usize,
+}
+
+pub fn default_page() -> usize {
+    1
+}
+
+pub fn default_page_size() -> usize {
+    20
+}
+
+impl Default for ListState {
+    fn default() -> ListState {
+        ListState {
+            page: default_page(),
+            page_size: default_page_size(),
+        }
+    }
+}
+
+/// Where the state of the list is kept, `<LIST>.state.json`.
+pub fn get_state_path(meta_path: &Path) -> PathBuf {
+    meta_path.with_extension("state.json")
+}
+
+/// Reads the state of the list, starting over when it's missing or broken
+/// as it's easily rebuilt.
+pub fn read_list_state(meta_path: &Path) -> ListState {
+    let Ok(raw) = fs::read_to_string(get_state_path(meta_path)) else {
+        return ListState::default();
+    };
+
+    serde_json::from_str(&raw).unwrap_or_default()
+}
+
+pub fn write_list_state(meta_path: &Path, state: &ListState) -> io::Result<()> {
+    fs::write(
+        get_state_path(meta_path),
+        serde_json::to_string(state).unwrap(),
+    )
+}
+
+/// Narrows the list to one page of `state.page_size` tasks, in the order
+/// `list` shows them so subtasks stay with their parent. The page is `page`
+/// if given, else the remembered one moved by `delta`, kept within the pages
+/// there are. A page size of 0 from a hand-edited state file counts as 1.
+/// Updates `state` and returns the page and how many pages there are.
+pub fn page_tasks(
+    tasklist: &TaskList,
+    state: &mut ListState,
+    page: Option<usize>,
+    delta: Option<isize>,
+) -> (TaskList, usize) {
+    state.page_size = state.page_size.max(1);
+
+    let pages = tasklist.tasks.len().div_ceil(state.page_size).max(1);
+
+    state.page = match (page, delta) {
+        (Some(x), _) => x,
+        (None, Some(x)) => state.page.saturating_add_signed(x),
+        (None, None) => state.page,
+    };
+    state.page = state.page.clamp(1, pages);
+
+    let shown: Vec<usize> = displayed_order(tasklist)
+        .into_iter()
+        .skip((state.page - 1) * state.page_size)
+        .take(state.page_size)
+        .collect();
+
+    (filter_tasks(tasklist, |x| shown.contains(&x.id)), pages)
+}
+
+/// The copy of the list taken before each save, `<LIST>.json.bak`.
+pub fn get_backup_path(meta_path: &Path) -> PathBuf {
+    meta_path.with_extension("json.bak")
+}
+
+/// Where the snapshots of the lists in the same directory as `meta_path`
+/// are kept.
+pub fn get_snapshot_dir(meta_path: &Path) -> PathBuf {
+    let dir = match meta_path.parent() {
+        Some(x) if x.as_os_str() != "" => x.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    dir.join(".planner-backups")
+}
+
+const SNAPSHOT_STAMP_FORMAT: &str = "%Y-%m-%dT%H-%M-%S%.3f";
+
+/// The snapshots of a list as their timestamp and path, newest first.
+pub fn list_snapshots(meta_path: &Path) -> Vec<(String, PathBuf)> {
+    let prefix = format!("{}-", meta_path.file_stem().unwrap().to_string_lossy());
+    let mut snapshots: Vec<(String, PathBuf)> = vec![];
+
+    let Ok(entries) = fs::read_dir(get_snapshot_dir(meta_path)) else {
+        return snapshots;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        let Some(stamp) = name
+            .strip_prefix(&prefix)
+            .and_then(|x| x.strip_suffix(".json"))
+        else {
+            continue;
+        };
+
+        // Lists can share a prefix, like "work" and "work-old"
+        if NaiveDateTime::parse_from_str(stamp, SNAPSHOT_STAMP_FORMAT).is_err() {
+            continue;
+        }
+
+        snapshots.push((stamp.to_string(), entry.path()));
+    }
+
+    snapshots.sort_by(|a, b| b.0.cmp(&a.0));
+
+    snapshots
+}
+
+/// Copies the list as it is now into the snapshot directory, then removes
+/// all but the newest `keep` snapshots.
+pub fn take_snapshot(meta_path: &Path, keep: usize) -> io::Result<()> {
+    if keep == 0 {
+        return Ok(());
+    }
+
+    let dir = get_snapshot_dir(meta_path);
+    fs::create_dir_all(&dir)?;
+
+    let name = format!(
+        "{}-{}.json",
+        meta_path.file_stem().unwrap().to_string_lossy(),
+        Local::now().format(SNAPSHOT_STAMP_FORMAT)
+    );
+
+    fs::copy(meta_path, dir.join(name))?;
+
+    for (_, path) in list_snapshots(meta_path).iter().skip(keep) {
+        fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+/// Saves the list to `meta_path`, keeping a backup and a snapshot of the
+/// previous version. Returns warnings about the steps after the write that
+/// failed without undoing it, the audit log and the git commit.
+pub fn write_task_list(
+    meta_path: &Path,
+    tasklist: &TaskList,
+    config: &Config,
+) -> io::Result<Vec<String>> {
+    let mut old = TaskList::default();
+
+    if let Ok(raw) = fs::read_to_string(meta_path)
+        && let Ok(x) = serde_json::from_str(raw.as_str())
+    {
+        old = x;
+    }
+
+    let had_backup = meta_path.exists();
+
+    if had_backup {
+        fs::copy(meta_path, get_backup_path(meta_path))?;
+        take_snapshot(meta_path, config.snapshot_count)?;
+    }
+
+    let stamped = TaskList {
+        version: Some(SCHEMA_VERSION),
+        ..tasklist.clone()
+    };
+    let raw = serde_json::to_string(&stamped).unwrap();
+
+    write_verified(meta_path, &raw, had_backup)?;
+
+    let mut warnings: Vec<String> = vec![];
+
+    if let Err(e) = append_to_audit_log(meta_path, &old, tasklist, config) {
+        warnings.push(format!("could not write to the audit log: {e}"));
+    }
+
+    if let Err(e) = git_commit_task_list(meta_path, &old, tasklist, config) {
+        warnings.push(e);
+    }
+
+    Ok(warnings)
+}
+
+/// Writes the serialized list `raw` and reads the file back, so a bad write
+/// is undone before anything relies on it: the backup is restored, or with
+/// none the file is removed.
+fn write_verified(meta_path: &Path, raw: &str, had_backup: bool) -> io::Result<()> {
+    fs::write(meta_path, raw)?;
+
+    let written = fs::read_to_string(meta_path).unwrap_or_default();
+    let reparsed = serde_json::from_str::<TaskList>(&written)
+        .is_ok_and(|x| serde_json::to_string(&x).is_ok_and(|x| x == raw));
+
+    if !reparsed {
+        if had_backup {
+            fs::copy(get_backup_path(meta_path), meta_path)?;
+        } else {
+            fs::remove_file(meta_path)?;
+        }
+
+        return Err(io::Error::other(
+            "the written list didn't read back the same, the previous version was restored",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Rebuilds a task list by applying every entry of an audit log, in order,
+/// to an empty list. Entries that don't fit the state built so far, like
+/// removing a task that doesn't exist, are applied as best as possible and
+/// reported as warnings.
+pub fn replay_audit_log(raw: &str) -> Result<(TaskList, Vec<String>), PlannerError> {
+    let mut tasklist = TaskList::default();
+    let mut warnings: Vec<String> = vec![];
+
+    for (n, line) in raw.lines().enumerate() {
+        if line.trim() == "" {
+            continue;
+        }
+
+        let entry: LogEntry = match serde_json::from_str(line) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(PlannerError::Parse(format!(
+                    "Invalid audit log entry on line {}: {e}",
+                    n + 1
+                )));
+            }
+        };
+
+        match entry.action {
+            LogAction::Add { task } => match get_task_index(&tasklist, task.id) {
+                Some(idx) => {
+                    warnings.push(format!(
+                        "Line {}: task #{} added twice, keeping the later one",
+                        n + 1,
+                        task.id
+                    ));
+                    tasklist.tasks[idx] = task;
+                }
+                None => tasklist.tasks.push(task),
+            },
+            LogAction::Update { task } => match get_task_index(&tasklist, task.id) {
+                Some(idx) => tasklist.tasks[idx] = task,
+                None => {
+                    warnings.push(format!(
+                        "Line {}: task #{} updated before it was added",
+                        n + 1,
+                        task.id
+                    ));
+                    tasklist.tasks.push(task);
+                }
+            },
+            LogAction::Remove { id } => match get_task_index(&tasklist, id) {
+                Some(idx) => {
+                    tasklist.tasks.remove(idx);
+                }
+                None => warnings.push(format!(
+                    "Line {}: task #{id} removed before it was added",
+                    n + 1
+                )),
+            },
+        }
+    }
+
+    Ok((tasklist, warnings))
+}
+
+/// Parses a user supplied date. Accepted forms are:
+///
+/// - `yyyy-mm-dd HH:MM:SS[.fff]`, interpreted in the local timezone
+/// - RFC3339, e.g. `2025-06-01T09:00:00+02:00` or `2025-06-01T07:00:00.250Z`,
+///   converted to the same instant in the local timezone
+pub fn parse_time(date: &str) -> Result<DateTime<Local>, PlannerError> {
+    if let Ok(native) = NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S%.f")
+        && let Some(actual) = Local.from_local_datetime(&native).earliest()
+    {
+        return Ok(actual);
+    }
+
+    if let Ok(fixed) = DateTime::parse_from_rfc3339(date) {
+        return Ok(fixed.with_timezone(&Local));
+    }
+
+    Err(PlannerError::Parse(format!(
+        "Invalid date '{date}', use 'yyyy-mm-dd HH:MM:SS' or RFC3339"
+    )))
+}
+
+/// Checks a strftime format before any date is formatted with it, as chrono
+/// panics on formats it can't parse.
+pub fn validate_date_format(format: &str) -> Result<(), PlannerError> {
+    if StrftimeItems::new(format).any(|x| matches!(x, Item::Error)) {
+        return Err(PlannerError::Parse(format!(
+            "Invalid date format '{format}'"
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn split_comma_list(list: &str) -> Vec<String> {
+    let mut new_vec: Vec<String> = vec![];
+
+    for s in list.split(",") {
+        let new_str = s.trim().to_string();
+
+        if !new_str.is_empty() {
+            new_vec.push(new_str);
+        }
+    }
+
+    new_vec
+}
+
+/// Percentage of `total` points that have been earned, 0 when there are no
+/// points at all. Point sums are `u64` so many large tasks can't overflow.
+/// " + N bonus" after a points figure, or nothing without a bonus.
+pub fn bonus_suffix(bonus: u64) -> String {
+    if bonus == 0 {
+        return String::new();
+    }
+
+    format!(" + {bonus} bonus")
+}
+
+pub fn completion_percentage(earned: u64, total: u64) -> u32 {
+    if total == 0 {
+        return 0;
+    }
+
+    ((earned as f64) / (total as f64) * 100.0) as u32
+}
+
+/// How a completion percentage should be highlighted, bad below
+/// `summary_warn_below`, good above `summary_good_above` and a warning in
+/// between.
+pub fn summary_status(perc: u32, config: &Config) -> Status {
+    if perc < config.summary_warn_below {
+        return Status::Bad;
+    } else if perc > config.summary_good_above {
+        return Status::Good;
+    }
+
+    Status::Warning
+}
+
+pub fn budget_warning(config: &Config, openpoints: u64, scheme: ColorScheme) -> Option<String> {
+    if let Some(budget) = config.points_budget {
+        let budget = budget as u64;
+
+        if openpoints > budget {
+            let msg = format!(
+                "Over budget by {} points ({openpoints}/{budget} open)",
+                openpoints - budget
+            );
+
+            return Some(colorize(&msg, Status::Bad, scheme));
+        }
+    }
+
+    None
+}
+
+/// The numbers shown by `stats`. Printed as-is by `stats --json`, so fields
+/// may be added but never renamed or removed.
+#[derive(Debug, Clone, Serialize)]
+pub struct Stats {
+    pub tasks: u32,
+    pub completed: u32,
+    pub points_earned: u64,
+    /// Without streak bonuses or late penalties, which only count towards
+    /// `points_earned`
+    pub points_total: u64,
+    /// The streak bonus part of `points_earned`
+    pub points_bonus: u64,
+    /// Points of open tasks, what `points_budget` is checked against
+    pub points_open: u64,
+    /// `points_earned` without `points_bonus` as a whole percentage of
+    /// `points_total`, so never above 100
+    pub percent: u32,
+    /// Open tasks whose due date has passed
+    pub overdue: u32,
+    /// The earliest due date of an open task
+    #[serde(with = "utc_time_opt")]
+    pub next_due: Option<DateTime<Local>>,
+    /// Only present with `--by-tag`. Untagged tasks are under "(untagged)".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by_tag: Option<BTreeMap<String, TagStats>>,
+}
+
+/// The points of open tasks still left to do after their progress, and the
+/// points of all open tasks.
+pub fn remaining_points(tasklist: &TaskList) -> (u64, u64) {
+    let mut remaining: u64 = 0;
+    let mut open: u64 = 0;
+
+    for task in tasklist.tasks.iter() {
+        if task.complete {
+            continue;
+        }
+
+        let points = task.points as u64;
+        let left = 100 - task_progress(task) as u64;
+
+        open += points;
+        remaining += points * left / 100;
+    }
+
+    (remaining, open)
+}
+
+/// The footer line for `--remaining-only`, which leaves completed tasks out.
+pub fn remaining_summary(tasklist: &TaskList, config: &Config, scheme: ColorScheme) -> String {
+    let (remaining, open) = remaining_points(tasklist);
+    let perc = completion_percentage(open - remaining, open);
+
+    let msg =
+        format!("Remaining points: {remaining} of {open} in open tasks ({perc}% of that done)");
+
+    colorize(&msg, summary_status(perc, config), scheme)
+}
+
+/// The totals shown under `list`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PointsSummary {
+    /// Points of completed tasks, plus the part done of open ones when
+    /// weighted by progress
+    pub earned: u64,
+    /// Points of all tasks, without streak bonuses or late penalties as
+    /// those only change what was earned
+    pub total: u64,
+    /// The streak bonus part of `earned`
+    pub bonus: u64,
+    /// Points of open tasks
+    pub open: u64,
+    /// Open tasks more than the grace period past due
+    pub overdue: usize,
+}
+
+/// Adds up the points of the list, counting tasks overdue only once they are
+/// more than `grace` past due. With `weight_progress` open tasks count for
+/// the part of their points they are done.
+pub fn summarize_points(
+    tasklist: &TaskList,
+    now: DateTime<Local>,
+    grace: TimeDelta,
+    weight_progress: bool,
+) -> PointsSummary {
+    let mut summary = PointsSummary::default();
+
+    for task in tasklist.tasks.iter() {
+        let points = task.points as u64;
+
+        if task.is_overdue_after(now, grace) {
+            summary.overdue += 1;
+        }
+
+        summary.total += points;
+
+        if task.complete {
+            summary.earned += task.total_points();
+            summary.bonus += task.earned_bonus();
+        } else {
+            summary.open += points;
+
+            if weight_progress {
+                summary.earned += points * task_progress(task) as u64 / 100;
+            }
+        }
+    }
+
+    summary
+}
+
+/// Works out the `stats` numbers at `now`, counting tasks overdue only once
+/// they are more than `grace` past due, like `list` does.
+pub fn compute_stats(
+    tasklist: &TaskList,
+    now: DateTime<Local>,
+    grace: TimeDelta,
+    by_tag: bool,
+) -> Stats {
+    let mut stats = Stats {
+        tasks: 0,
+        completed: 0,
+        points_earned: 0,
+        points_total: 0,
+        points_bonus: 0,
+        points_open: 0,
+        percent: 0,
+        overdue: 0,
+        next_due: None,
+        by_tag: None,
+    };
+
+    for task in tasklist.tasks.iter() {
+        stats.tasks += 1;
+        stats.points_total += task.points as u64;
+
+        if task.complete {
+            stats.completed += 1;
+            stats.points_earned += task.total_points();
+            stats.points_bonus += task.earned_bonus();
+            continue;
+        }
+
+        stats.points_open += task.points as u64;
+
+        if task.is_overdue_after(now, grace) {
+            stats.overdue += 1;
+        }
+
+        if let Some(x) = task.due_date
+            && stats.next_due.is_none_or(|y| x < y)
+        {
+            stats.next_due = Some(x);
+        }
+    }
+
+    stats.percent =
+        completion_percentage(stats.points_earned - stats.points_bonus, stats.points_total);
+
+    if by_tag {
+        let mut tags: BTreeMap<String, TagStats> = BTreeMap::new();
+
+        for task in tasklist.tasks.iter() {
+            if task.tags.is_empty() {
+                tags.entry("(untagged)".to_string()).or_default().add(task);
+            }
+
+            for tag in task.tags.iter() {
+                tags.entry(tag.clone()).or_default().add(task);
+            }
+        }
+
+        stats.by_tag = Some(tags);
+    }
+
+    stats
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TagStats {
+    pub tasks: u32,
+    pub completed: u32,
+    pub earned: u64,
+    pub total: u64,
+    /// The streak bonus part of `earned`
+    pub bonus: u64,
+}
+
+impl TagStats {
+    pub fn add(&mut self, task: &Task) {
+        self.tasks += 1;
+        self.total += task.points as u64;
+
+        if task.complete {
+            self.completed += 1;
+            self.earned += task.total_points();
+            self.bonus += task.earned_bonus();
+        }
+    }
+
+    /// A line like "  work: 3 tasks, 1 completed, 2/8 points (25%)", with
+    /// any streak bonus after the percentage.
+    pub fn describe(&self, tag: &str) -> String {
+        let earned = self.earned - self.bonus;
+
+        format!(
+            "  {tag}: {} tasks, {} completed, {earned}/{} points ({}%){}",
+            self.tasks,
+            self.completed,
+            self.total,
+            completion_percentage(earned, self.total),
+            bonus_suffix(self.bonus)
+        )
+    }
+}
+
+/// Reads tasks from a planner JSON file, keeping their parent and
+/// dependency links but not their ids.
+pub fn parse_json_tasks(raw: &str) -> Result<Vec<Task>, PlannerError> {
+    let task_list: TaskList = match serde_json::from_str(raw) {
+        Ok(x) => x,
+        Err(e) => return Err(PlannerError::Parse(format!("Invalid task file: {e}"))),
+    };
+
+    Ok(task_list.tasks)
+}
+
+/// Reads tasks from JSON Lines, one task object per non-empty line.
+pub fn parse_jsonl_tasks(raw: &str) -> Result<Vec<Task>, PlannerError> {
+    let mut tasks: Vec<Task> = vec![];
+
+    for (i, line) in raw.lines().enumerate() {
+        if line.trim() == "" {
+            continue;
+        }
+
+        match serde_json::from_str(line) {
+            Ok(x) => tasks.push(x),
+            Err(e) => {
+                return Err(PlannerError::Parse(format!(
+                    "Invalid task on line {}: {e}",
+                    i + 1
+                )));
+            }
+        }
+    }
+
+    Ok(tasks)
+}
+
+/// Writes each task as a JSON object on its own line.
+pub fn generate_jsonl(tasklist: &TaskList) -> String {
+    let mut out = String::new();
+
+    for task in tasklist.tasks.iter() {
+        out += serde_json::to_string(task).unwrap().as_str();
+        out += "\n";
+    }
+
+    out
+}
+
+/// Reads tasks from a Markdown checklist. Every `- [ ] name` or `- [x] name`
+/// line (also with `*`) becomes a task, and a trailing `(N points)` sets its
+/// points. Other lines are ignored.
+pub fn parse_markdown_tasks(raw: &str) -> Vec<Task> {
+    let mut tasks: Vec<Task> = vec![];
+
+    for line in raw.lines() {
+        let line = line.trim();
+
+        let Some(item) = line.strip_prefix("- ").or(line.strip_prefix("* ")) else {
+            continue;
+        };
+
+        let mut complete = false;
+        let mut name = item.trim();
+
+        if let Some(x) = name.strip_prefix("[ ]") {
+            name = x.trim();
+        } else if let Some(x) = name.strip_prefix("[x]").or(name.strip_prefix("[X]")) {
+            name = x.trim();
+            complete = true;
+        } else {
+            continue;
+        }
+
+        let mut points = 0;
+
+        if let Some(open) = name.rfind(" (")
+            && let Some(x) = name[open + 2..].strip_suffix(" points)")
+            && let Ok(x) = x.parse::<u32>()
+        {
+            points = x;
+            name = name[..open].trim();
+        }
+
+        if name.is_empty() {
+            continue;
+        }
+
+        let mut task = Task::new(tasks.len(), name.to_string(), points);
+        task.complete = complete;
+        if complete {
+            task.completed_at = Some(Local::now());
+        }
+
+        tasks.push(task);
+    }
+
+    tasks
+}
+
+/// Writes the list as a Markdown checklist that `parse_markdown_tasks` can
+/// read back, subtasks indented under their parent. With `details`, each
+/// line ends with the points and any due date, like `(3 points, due ...)`.
+pub fn render_markdown(tasklist: &TaskList, details: bool, date_format: &str) -> String {
+    let mut out = String::new();
+
+    for id in displayed_order(tasklist) {
+        let task = &tasklist.tasks[get_task_index(tasklist, id).unwrap()];
+
+        let mut depth = 0;
+        let mut parent = task.parent;
+
+        while let Some(x) = parent.and_then(|x| get_task_index(tasklist, x)) {
+            depth += 1;
+            parent = tasklist.tasks[x].parent;
+        }
+
+        let mark = if task.complete { "x" } else { " " };
+        let mut line = format!("{}- [{mark}] {}", "  ".repeat(depth), task.name);
+
+        if details {
+            match task.due_date {
+                Some(x) => {
+                    line += format!(" ({} points, due {})", task.points, x.format(date_format))
+                        .as_str();
+                }
+                None => line += format!(" ({} points)", task.points).as_str(),
+            }
+        }
+
+        out += format!("{line}\n").as_str();
+    }
+
+    out
+}
+
+/// Gives `tasks` the ids 0..n in order, rewriting parent and dependency
+/// links to match and dropping links to tasks that aren't in the set.
+pub fn renumber_tasks(tasks: &mut [Task]) {
+    let old_ids: Vec<usize> = tasks.iter().map(|t| t.id).collect();
+
+    let new_id = |old: usize| old_ids.iter().position(|x| *x == old);
+
+    for (i, task) in tasks.iter_mut().enumerate() {
+        task.id = i;
+        task.parent = task.parent.and_then(new_id);
+        task.depends_on = task.depends_on.iter().filter_map(|x| new_id(*x)).collect();
+    }
+}
+
+/// Gives every task the id `first` plus its position in `order`, which has
+/// the current ids of all tasks, rewriting parent and dependency links.
+pub fn remap_ids(tasklist: &mut TaskList, order: &[usize], first: usize) {
+    let new_id = |old: usize| order.iter().position(|x| *x == old).map(|x| x + first);
+
+    for task in tasklist.tasks.iter_mut() {
+        task.id = new_id(task.id).unwrap();
+        task.parent = task.parent.and_then(new_id);
+        task.depends_on = task.depends_on.iter().filter_map(|x| new_id(*x)).collect();
+    }
+}
+
+/// Adds `tasks` to the list under ids after the highest one in use, keeping
+/// the links between them. Tasks sharing a uuid with one already in the
+/// list get a new one. Returns how many were added.
+pub fn append_tasks(tasklist: &mut TaskList, mut tasks: Vec<Task>) -> usize {
+    renumber_tasks(&mut tasks);
+
+    for task in tasks.iter_mut() {
+        if task.uuid.is_none() || find_task_by_uuid(tasklist, task.uuid.as_ref().unwrap()).is_some()
+        {
+            task.uuid = Some(Uuid::new_v4().to_string());
+        }
+    }
+
+    let base = match tasklist.tasks.iter().map(|x| x.id).max() {
+        Some(x) => x + 1,
+        None => 0,
+    };
+
+    for task in tasks.iter_mut() {
+        task.id += base;
+        task.parent = task.parent.map(|x| x + base);
+        task.depends_on = task.depends_on.iter().map(|x| x + base).collect();
+    }
+
+    let count = tasks.len();
+    tasklist.tasks.append(&mut tasks);
+
+    count
+}
+
+/// Replaces the tag `old` with `new` on every task, keeping only one of them
+/// on tasks that already had both. Returns how many tasks had `old`.
+pub fn rename_tag(tasklist: &mut TaskList, old: &str, new: &str) -> usize {
+    // Nothing to change, and the loop below would drop the tag as a duplicate
+    if old == new {
+        return tasklist
+            .tasks
+            .iter()
+            .filter(|x| x.tags.iter().any(|x| x == old))
+            .count();
+    }
+
+    let mut count = 0;
+
+    for task in tasklist.tasks.iter_mut() {
+        let Some(pos) = task.tags.iter().position(|x| x == old) else {
+            continue;
+        };
+
+        if task.tags.iter().any(|x| x == new) {
+            task.tags.remove(pos);
+        } else {
+            task.tags[pos] = new.to_string();
+        }
+
+        count += 1;
+    }
+
+    count
+}
+
+/// Takes `tag` off every task, returning how many had it.
+pub fn remove_tag(tasklist: &mut TaskList, tag: &str) -> usize {
+    let mut count = 0;
+
+    for task in tasklist.tasks.iter_mut() {
+        let before = task.tags.len();
+        task.tags.retain(|x| x != tag);
+
+        if task.tags.len() != before {
+            count += 1;
+        }
+    }
+
+    count
+}
+
+/// How many tasks have each tag.
+pub fn count_tags(tasklist: &TaskList) -> BTreeMap<String, usize> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+
+    for task in tasklist.tasks.iter() {
+        for tag in task.tags.iter() {
+            *counts.entry(tag.clone()).or_default() += 1;
+        }
+    }
+
+    counts
+}
+
+/// The position of the first task named `name`, ignoring case and
+/// surrounding whitespace.
+pub fn find_task_by_name(tasklist: &TaskList, name: &str) -> Option<usize> {
+    let wanted = name.trim().to_lowercase();
+
+    tasklist
+        .tasks
+        .iter()
+        .position(|x| x.name.trim().to_lowercase() == wanted)
+}
+
+/// The position of the task with the given uuid.
+pub fn find_task_by_uuid(tasklist: &TaskList, uuid: &str) -> Option<usize> {
+    tasklist
+        .tasks
+        .iter()
+        .position(|x| x.uuid.as_deref() == Some(uuid))
+}
+
+/// Adds the tasks of another list to this one under ids after the highest
+/// one in use, skipping the tasks whose uuid is already in the list. Links
+/// to a skipped task point to its copy in this list instead. Returns a line
+/// per task saying what happened to it.
+pub fn merge_task_list(tasklist: &mut TaskList, other: TaskList) -> Vec<String> {
+    let mut report: Vec<String> = vec![];
+
+    // Ids in `other` of the tasks already here, with their id in this list
+    let mut existing: Vec<(usize, usize)> = vec![];
+    let mut added: Vec<Task> = vec![];
+
+    for task in other.tasks {
+        let found = task
+            .uuid
+            .as_ref()
+            .and_then(|x| find_task_by_uuid(tasklist, x));
+
+        if let Some(idx) = found {
+            let id = tasklist.tasks[idx].id;
+
+            report.push(format!("Skipped '{}', it is already #{id}", task.name));
+            existing.push((task.id, id));
+            continue;
+        }
+
+        added.push(task);
+    }
+
+    let base = match tasklist.tasks.iter().map(|x| x.id).max() {
+        Some(x) => x + 1,
+        None => 0,
+    };
+
+    let old_ids: Vec<usize> = added.iter().map(|x| x.id).collect();
+
+    let new_id = |old: usize| match old_ids.iter().position(|x| *x == old) {
+        Some(x) => Some(base + x),
+        None => existing.iter().find(|x| x.0 == old).map(|x| x.1),
+    };
+
+    for task in added.iter_mut() {
+        task.id = new_id(task.id).unwrap();
+        task.parent = task.parent.and_then(new_id);
+        task.depends_on = task.depends_on.iter().filter_map(|x| new_id(*x)).collect();
+
+        if task.uuid.is_none() {
+            task.uuid = Some(Uuid::new_v4().to_string());
+        }
+
+        report.push(format!("Added '{}' as #{}", task.name, task.id));
+    }
+
+    tasklist.tasks.append(&mut added);
+
+    report
+}
+
+/// Adds imported tasks to the list, handling the ones named like an
+/// existing task according to `policy`. Returns a line per task saying what
+/// happened to it.
+pub fn merge_tasks(
+    tasklist: &mut TaskList,
+    incoming: Vec<Task>,
+    policy: MergePolicy,
+) -> Vec<String> {
+    let mut report: Vec<String> = vec![];
+    let mut appended: Vec<Task> = vec![];
+
+    for task in incoming {
+        let existing = match policy {
+            MergePolicy::Append => None,
+            _ => find_task_by_name(tasklist, &task.name),
+        };
+
+        let Some(idx) = existing else {
+            appended.push(task);
+            continue;
+        };
+
+        let old = &tasklist.tasks[idx];
+
+        if policy == MergePolicy::Skip {
+            report.push(format!(
+                "Skipped '{}', #{} has the same name",
+                task.name, old.id
+            ));
+            continue;
+        }
+
+        report.push(format!("Replaced #{} '{}'", old.id, task.name));
+
+        tasklist.tasks[idx] = Task {
+            id: old.id,
+            parent: old.parent,
+            depends_on: old.depends_on.clone(),
+            ..task
+        };
+    }
+
+    let count = append_tasks(tasklist, appended);
+
+    for task in tasklist.tasks[tasklist.tasks.len() - count..].iter() {
+        report.push(format!("Added '{}' as #{}", task.name, task.id));
+    }
+
+    report
+}
+
+/// A few tasks showing off points, due dates, tags and subtasks, for
+/// `init --sample`.
+pub fn sample_tasks(now: DateTime<Local>) -> Vec<Task> {
+    let mut done = Task::builder(0, "Set up planner".to_string(), 1)
+        .tags(vec!["getting-started".to_string()])
+        .build();
+
+    done.complete = true;
+    done.completed_at = Some(now);
+
+    vec![
+        done,
+        Task::builder(1, "Plan the week".to_string(), 2)
+            .due_date(Some(now + TimeDelta::days(2)))
+            .tags(vec!["planning".to_string()])
+            .priority(2)
+            .build(),
+        Task::builder(2, "Write the report".to_string(), 5)
+            .due_date(Some(now + TimeDelta::days(7)))
+            .tags(vec!["work".to_string()])
+            .build(),
+        Task::builder(3, "Outline the report".to_string(), 3)
+            .parent(Some(2))
+            .tags(vec!["work".to_string()])
+            .build(),
+    ]
+}
+
+/// Loads the starter tasks for `init --template`, as fresh incomplete tasks.
+pub fn read_template(path: &str) -> Result<Vec<Task>, PlannerError> {
+    let Ok(raw) = fs::read_to_string(path) else {
+        return Err(PlannerError::Generic(format!(
+            "Could not read template '{path}'"
+        )));
+    };
+
+    let mut tasks = if path.ends_with(".md") || path.ends_with(".markdown") {
+        parse_markdown_tasks(&raw)
+    } else {
+        parse_json_tasks(&raw)?
+    };
+
+    renumber_tasks(&mut tasks);
+
+    let now = Local::now();
+
+    for task in tasks.iter_mut() {
+        task.reset_completion();
+        task.uuid = Some(Uuid::new_v4().to_string());
+        task.created_at = Some(now);
+    }
+
+    Ok(tasks)
+}
+
+pub fn get_task_index(tasklist: &TaskList, id: usize) -> Option<usize> {
+    tasklist.tasks.iter().position(|t| t.id == id)
+}
+
+/// Parses a priority given as a number or as `low`, `medium` or `high`,
+/// which stand for 1, 2 and 3.
+pub fn parse_priority(priority: &str) -> Result<u8, PlannerError> {
+    match priority.trim().to_lowercase().as_str() {
+        "low" => Ok(1),
+        "medium" => Ok(2),
+        "high" => Ok(3),
+        x => match x.parse::<u8>() {
+            Ok(x) => Ok(x),
+            Err(_) => Err(PlannerError::Parse(format!(
+                "Invalid priority '{priority}', use a number or low, medium or high"
+            ))),
+        },
+    }
+}
+
+/// Parses a duration such as `30m`, `2h`, `3d`, `1w` or `1d12h`.
+pub fn parse_duration(duration: &str) -> Result<TimeDelta, PlannerError> {
+    let invalid = || {
+        PlannerError::Parse(format!(
+            "Invalid duration '{duration}', use a number followed by m, h, d or w, e.g. '2h'"
+        ))
+    };
+
+    let mut total = TimeDelta::zero();
+    let mut number = String::new();
+
+    for c in duration.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let Ok(amount) = number.parse::<i64>() else {
+            return Err(invalid());
+        };
+        number.clear();
+
+        let unit = match c {
+            'm' => TimeDelta::try_minutes(amount),
+            'h' => TimeDelta::try_hours(amount),
+            'd' => TimeDelta::try_days(amount),
+            'w' => TimeDelta::try_weeks(amount),
+            _ => None,
+        };
+
+        match unit.and_then(|x| total.checked_add(&x)) {
+            Some(x) => total = x,
+            None => return Err(invalid()),
+        }
+    }
+
+    if !number.is_empty() || total == TimeDelta::zero() {
+        return Err(invalid());
+    }
+
+    Ok(total)
+}
+
+/// Checks that an edited task still fits in the list: its points are within
+/// the configured maximum and its parent and dependencies exist.
+pub fn validate_task(
+    tasklist: &TaskList,
+    task: &Task,
+    config: &Config,
+) -> Result<(), PlannerError> {
+    if task.name.trim() == "" {
+        return Err(PlannerError::Generic(
+            "A task name can't be empty".to_string(),
+        ));
+    }
+
+    if let Some(max) = config.max_points
+        && task.points > max
+    {
+        return Err(PlannerError::Generic(format!(
+            "A task can be worth at most {max} points"
+        )));
+    }
+
+    if let Some(x) = task.parent {
+        if x == task.id {
+            return Err(PlannerError::Generic(
+                "A task can't be its own parent".to_string(),
+            ));
+        }
+
+        if get_task_index(tasklist, x).is_none() {
+            return Err(PlannerError::TaskNotFound(x));
+        }
+    }
+
+    for dep in task.depends_on.iter() {
+        if *dep == task.id {
+            return Err(PlannerError::Generic(
+                "A task can't depend on itself".to_string(),
+            ));
+        }
+
+        if get_task_index(tasklist, *dep).is_none() {
+            return Err(PlannerError::TaskNotFound(*dep));
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds a new task to the list once `validate_task` accepts it, then fits
+/// the dates of its top level parent around the subtasks.
+pub fn add_task(tasklist: &mut TaskList, task: Task, config: &Config) -> Result<(), PlannerError> {
+    if get_task_index(tasklist, task.id).is_some() {
+        return Err(PlannerError::Generic(format!(
+            "Task id {} is already taken",
+            task.id
+        )));
+    }
+
+    validate_task(tasklist, &task, config)?;
+
+    let mut root = task.parent;
+    tasklist.tasks.push(task);
+
+    while let Some(x) = root {
+        let idx = get_task_index(tasklist, x).unwrap();
+
+        if tasklist.tasks[idx].parent.is_none() {
+            fit_task_size_to_children(tasklist, x);
+            break;
+        }
+
+        root = tasklist.tasks[idx].parent;
+    }
+
+    Ok(())
+}
+
+/// An open copy of the task `src` under the id `id`, with its own uuid and
+/// no progress.
+pub fn duplicate_task(
+    tasklist: &TaskList,
+    src: usize,
+    id: usize,
+    now: DateTime<Local>,
+) -> Result<Task, PlannerError> {
+    let Some(idx) = get_task_index(tasklist, src) else {
+        return Err(PlannerError::TaskNotFound(src));
+    };
+
+    let mut copy = tasklist.tasks[idx].clone();
+
+    copy.id = id;
+    copy.reset_completion();
+
+    if let Some(x) = copy.recurrence.as_mut() {
+        x.next_id = None;
+    }
+    copy.uuid = Some(Uuid::new_v4().to_string());
+    copy.created_at = Some(now);
+
+    Ok(copy)
+}
+
+/// Reads back a task edited as JSON, keeping the id of `original` as it
+/// can't be changed.
+pub fn parse_edited_task(raw: &str, original: &Task) -> Result<Task, PlannerError> {
+    let mut edited: Task = match serde_json::from_str(raw) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(PlannerError::Parse(format!(
+                "Invalid task, left unchanged: {e}"
+            )));
+        }
+    };
+
+    edited.id = original.id;
+
+    Ok(edited)
+}
+
+/// Fails for config values that parse but can't be used, like durations or
+/// tag colors planner doesn't understand.
+pub fn validate_config(config: &Config) -> Result<(), PlannerError> {
+    let durations = [
+        ("auto_archive_after", &config.auto_archive_after),
+        ("overdue_threshold", &config.overdue_threshold),
+    ];
+
+    for (key, value) in durations {
+        if let Some(x) = value
+            && let Err(e) = parse_duration(x)
+        {
+            return Err(PlannerError::Parse(format!("Invalid {key}: {e}")));
+        }
+    }
+
+    if let Some(x) = &config.date_format {
+        validate_date_format(x)?;
+    }
+
+    for (tag, name) in config.tag_colors.iter() {
+        if color_code(name).is_none() {
+            return Err(PlannerError::Parse(format!(
+                "Unknown color '{name}' for tag '{tag}'"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads back an edited config file, failing on unknown keys and on values
+/// `validate_config` rejects.
+pub fn parse_edited_config(raw: &str) -> Result<Config, PlannerError> {
+    let (value, config) = match (
+        serde_json::from_str::<serde_json::Value>(raw),
+        serde_json::from_str::<Config>(raw),
+    ) {
+        (Ok(x), Ok(y)) => (x, y),
+        (_, Err(e)) | (Err(e), _) => {
+            return Err(PlannerError::Parse(format!(
+                "Invalid config, left unchanged: {e}"
+            )));
+        }
+    };
+
+    let known = serde_json::to_value(&config).unwrap();
+
+    if let Some(field) = find_unknown_fields(&value, &known, "").first() {
+        return Err(PlannerError::Parse(format!(
+            "Unknown key '{field}', config left unchanged"
+        )));
+    }
+
+    if let Err(e) = validate_config(&config) {
+        return Err(PlannerError::Parse(format!("{e}, config left unchanged")));
+    }
+
+    Ok(config)
+}
+
+/// Checks off a task once all its subtasks are complete, pushing its next
+/// occurrence if it repeats. Returns that occurrence.
+pub fn complete_task(
+    tasklist: &mut TaskList,
+    id: usize,
+    completed_at: DateTime<Local>,
+    note: Option<String>,
+) -> Result<Option<Task>, PlannerError> {
+    let children = get_all_children_of_task(tasklist, id);
+
+    for child in children.iter() {
+        if !child.complete {
+            return Err(PlannerError::Generic(
+                "Cannot complete task, complete subtasks before!".to_string(),
+            ));
+        }
+    }
+
+    let Some(idx) = get_task_index(tasklist, id) else {
+        return Err(PlannerError::TaskNotFound(id));
+    };
+
+    let mut next: Option<Task> = None;
+
+    // Checking it off again after unchecking it mustn't repeat it twice
+    let spawned = tasklist.tasks[idx]
+        .recurrence
+        .as_ref()
+        .is_some_and(|x| x.next_id.is_some());
+
+    if !tasklist.tasks[idx].complete && !spawned {
+        let free = get_free_id(tasklist);
+        next = next_occurrence(&tasklist.tasks[idx], free, completed_at)?;
+    }
+
+    tasklist.tasks[idx].complete = true;
+    tasklist.tasks[idx].completed_at = Some(completed_at);
+    tasklist.tasks[idx].completion_note = note;
+
+    if let Some(x) = &next {
+        if let Some(recurrence) = tasklist.tasks[idx].recurrence.as_mut() {
+            recurrence.next_id = Some(x.id);
+        }
+
+        tasklist.tasks.push(x.clone());
+    }
+
+    Ok(next)
+}
+
+/// How many days in a row, counting back from the local day of `at`, at
+/// least one task was completed on.
+pub fn completion_streak(tasklist: &TaskList, at: DateTime<Local>) -> u32 {
+    let days: Vec<NaiveDate> = tasklist
+        .tasks
+        .iter()
+        .filter(|x| x.complete)
+        .filter_map(|x| x.completed_at)
+        .map(|x| x.date_naive())
+        .collect();
+
+    let mut streak = 0;
+    let mut day = at.date_naive();
+
+    while days.contains(&day) {
+        streak += 1;
+
+        let Some(previous) = day.pred_opt() else {
+            break;
+        };
+        day = previous;
+    }
+
+    streak
+}
+
+/// The bonus earned by a task completed at `at`: `streak_bonus_points` when
+/// the streak has just reached another multiple of `streak_bonus_days`, but
+/// only for the first task completed that day to be awarded it.
+pub fn streak_bonus(tasklist: &TaskList, at: DateTime<Local>, config: &Config) -> u32 {
+    if config.streak_bonus_days == 0 {
+        return 0;
+    }
+
+    let streak = completion_streak(tasklist, at);
+
+    if streak == 0 || !streak.is_multiple_of(config.streak_bonus_days) {
+        return 0;
+    }
+
+    let awarded = tasklist.tasks.iter().any(|x| {
+        x.complete
+            && x.bonus_points > 0
+            && x.completed_at
+                .is_some_and(|x| x.date_naive() == at.date_naive())
+    });
+
+    if awarded {
+        return 0;
+    }
+
+    config.streak_bonus_points
+}
+
+/// The points `task` loses when completed at `at`, `late_penalty_percent`
+/// of its points if that's after its due date. Completing it exactly when
+/// due is still on time.
+pub fn late_penalty(task: &Task, at: DateTime<Local>, config: &Config) -> u32 {
+    let Some(due) = task.due_date else {
+        return 0;
+    };
+
+    if config.late_penalty_percent == 0 || at <= due {
+        return 0;
+    }
+
+    let penalty = task.points as u64 * config.late_penalty_percent.min(100) as u64 / 100;
+
+    // At most the task's own points, so it always fits
+    u32::try_from(penalty).unwrap_or(u32::MAX)
+}
+
+/// What checking off a task did besides completing it.
+#[derive(Debug, Clone, Default)]
+pub struct CheckOutcome {
+    /// The next occurrence created for a recurring task
+    pub next: Option<Task>,
+    pub bonus: u32,
+    pub penalty: u32,
+    /// Dependencies also checked off for `close_deps`
+    pub closed: Vec<usize>,
+    /// Dependencies `close_deps` had to leave open
+    pub left_open: Vec<usize>,
+    /// Parents and dependents checked off for `cascade_complete`
+    pub cascaded: Vec<usize>,
+}
+
+/// Records on a task just checked off at `at` when it reopens, its streak
+/// bonus and its late penalty.
+fn record_check(
+    tasklist: &mut TaskList,
+    idx: usize,
+    at: DateTime<Local>,
+    reopen_at: Option<DateTime<Local>>,
+    config: &Config,
+) {
+    tasklist.tasks[idx].reopen_at = reopen_at;
+    // Checking it off again mustn't count its own earlier bonus as awarded
+    tasklist.tasks[idx].bonus_points = 0;
+    tasklist.tasks[idx].bonus_points = streak_bonus(tasklist, at, config);
+    tasklist.tasks[idx].penalty_points = late_penalty(&tasklist.tasks[idx], at, config);
+}
+
+/// Checks off `id` at `at` the way `check` does: with any streak bonus and
+/// late penalty, reopening at `reopen_at`, and checking off what it unblocks
+/// when the config cascades completions. With `close_deps` its open
+/// dependencies are checked off too.
+pub fn check_task(
+    tasklist: &mut TaskList,
+    id: usize,
+    at: DateTime<Local>,
+    note: Option<String>,
+    reopen_at: Option<DateTime<Local>>,
+    close_deps: bool,
+    config: &Config,
+) -> Result<CheckOutcome, PlannerError> {
+    let mut outcome = CheckOutcome::default();
+
+    if close_deps {
+        (outcome.closed, outcome.left_open) = close_dependencies(tasklist, id, at);
+    }
+
+    outcome.next = complete_task(tasklist, id, at, note)?;
+
+    let idx = get_task_index(tasklist, id).unwrap();
+
+    record_check(tasklist, idx, at, reopen_at, config);
+    outcome.bonus = tasklist.tasks[idx].bonus_points;
+    outcome.penalty = tasklist.tasks[idx].penalty_points;
+
+    if config.cascade_complete {
+        outcome.cascaded = cascade_completions(tasklist, id, at);
+    }
+
+    Ok(outcome)
+}
+
+/// Checks off the tasks in `ids` like `check_task`, each only once the tasks
+/// it depends on are complete, going round again as completing one can
+/// unblock another. Returns how many were checked off, the ids of those left
+/// open and the ids of those checked off by cascading completions.
+pub fn check_tasks(
+    tasklist: &mut TaskList,
+    ids: &[usize],
+    at: DateTime<Local>,
+    note: Option<String>,
+    reopen_at: Option<DateTime<Local>>,
+    config: &Config,
+) -> (usize, Vec<usize>, Vec<usize>) {
+    let mut pending = ids.to_vec();
+    let mut cascaded: Vec<usize> = vec![];
+    let mut count = 0;
+
+    loop {
+        let mut progressed = false;
+
+        for id in pending.clone() {
+            // Already checked off by a cascade this round
+            if !pending.contains(&id) {
+                continue;
+            }
+
+            let idx = get_task_index(tasklist, id).unwrap();
+
+            let blocked = tasklist.tasks[idx].depends_on.iter().any(|dep| {
+                get_task_index(tasklist, *dep).is_some_and(|x| !tasklist.tasks[x].complete)
+            });
+
+            if blocked {
+                continue;
+            }
+
+            if complete_task(tasklist, id, at, note.clone()).is_ok() {
+                record_check(tasklist, idx, at, reopen_at, config);
+                pending.retain(|x| *x != id);
+                count += 1;
+                progressed = true;
+
+                if config.cascade_complete {
+                    let more = cascade_completions(tasklist, id, at);
+
+                    pending.retain(|x| !more.contains(x));
+                    cascaded.extend(more);
+                }
+            }
+        }
+
+        if !progressed {
+            break;
+        }
+    }
+
+    (count, pending, cascaded)
+}
+
+/// Opens a checked off task again, with no progress.
+pub fn uncheck_task(tasklist: &mut TaskList, id: usize) -> Result<(), PlannerError> {
+    let Some(idx) = get_task_index(tasklist, id) else {
+        return Err(PlannerError::TaskNotFound(id));
+    };
+
+    tasklist.tasks[idx].reset_completion();
+
+    Ok(())
+}
+
+/// Sets how much of a task is done. 100 checks it off at `at`, returning its
+/// next occurrence if it repeats, and anything less opens it again.
+pub fn set_progress(
+    tasklist: &mut TaskList,
+    id: usize,
+    percent: u8,
+    at: DateTime<Local>,
+) -> Result<Option<Task>, PlannerError> {
+    let Some(idx) = get_task_index(tasklist, id) else {
+        return Err(PlannerError::TaskNotFound(id));
+    };
+
+    let mut next: Option<Task> = None;
+
+    if percent >= 100 {
+        next = complete_task(tasklist, id, at, None)?;
+    } else if tasklist.tasks[idx].complete {
+        tasklist.tasks[idx].reset_completion();
+    }
+
+    tasklist.tasks[idx].progress = Some(percent.min(100));
+
+    Ok(next)
+}
+
+/// How far along a task is, from 0 to 100. Completed tasks are always at 100.
+pub fn task_progress(task: &Task) -> u8 {
+    if task.complete {
+        return 100;
+    }
+
+    task.progress.unwrap_or(0)
+}
+
+/// A ten cell bar like `[###-------]` showing `progress` percent.
+pub fn progress_bar(progress: u8) -> String {
+    let filled = (progress as usize * 10) / 100;
+
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(10 - filled))
+}
+
+/// The task to create when `task` is checked off, or `None` when it doesn't
+/// repeat or its recurrence has ended. Fails when its dates can't be moved
+/// forward by the recurrence.
+pub fn next_occurrence(
+    task: &Task,
+    id: usize,
+    now: DateTime<Local>,
+) -> Result<Option<Task>, PlannerError> {
+    let Some(recurrence) = task.recurrence.clone() else {
+        return Ok(None);
+    };
+
+    if recurrence.remaining.is_some_and(|x| x <= 1) {
+        return Ok(None);
+    }
+
+    let every = recurrence.every()?;
+    let out_of_range = || {
+        PlannerError::Parse(format!(
+            "The next occurrence of '{}' would be out of range",
+            task.name
+        ))
+    };
+
+    let due_date = task
+        .due_date
+        .unwrap_or(now)
+        .checked_add_signed(every)
+        .ok_or_else(out_of_range)?;
+
+    let start_time = match task.start_time {
+        Some(x) => Some(x.checked_add_signed(every).ok_or_else(out_of_range)?),
+        None => None,
+    };
+
+    if let Some(until) = recurrence.until
+        && due_date > until
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(Task {
+        due_date: Some(due_date),
+        start_time,
+        parent: task.parent,
+        resources: task.resources.clone(),
+        tags: task.tags.clone(),
+        reminders: task.reminders.clone(),
+        recurrence: Some(Recurrence {
+            remaining: recurrence.remaining.map(|x| x - 1),
+            next_id: None,
+            ..recurrence
+        }),
+        ..Task::new(id, task.name.clone(), task.points)
+    }))
+}
+
+pub fn get_free_id(tasklist: &TaskList) -> usize {
+    let mut id: usize = 0;
+
+    loop {
+        let mut found = false;
+
+        for i in 0..tasklist.tasks.len() {
+            if id == tasklist.tasks[i].id {
+                found = true;
+                break;
+            }
+        }
+
+        if !found {
+            break;
+        }
+
+        id += 1;
+    }
+
+    id
+}
+
+pub fn get_all_children_of_task(tasklist: &TaskList, parent: usize) -> Vec<Task> {
+    let mut children: Vec<Task> = vec![];
+
+    for i in 0..tasklist.tasks.len() {
+        if let Some(x) = tasklist.tasks[i].parent
+            && x == parent
+        {
+            children.push(tasklist.tasks[i].clone());
+        }
+    }
+
+    children
+}
+
+pub fn task_has_children(tasklist: &TaskList, id: usize) -> bool {
+    for i in 0..tasklist.tasks.len() {
+        if tasklist.tasks[i].id == id {
+            return !get_all_children_of_task(tasklist, i).is_empty();
+        }
+    }
+
+    false
+}
+
+pub fn fit_task_size_to_children(
+    tasklist: &mut TaskList,
+    id: usize,
+) -> (Option<DateTime<Local>>, Option<DateTime<Local>>) {
+    let children = get_all_children_of_task(tasklist, id);
+
+    let mut start_time: Option<DateTime<Local>> = None;
+    let mut end_time: Option<DateTime<Local>> = None;
+
+    for child in children.iter() {
+        let mut dates = (child.start_time, child.due_date);
+
+        if task_has_children(tasklist, child.id) {
+            dates = fit_task_size_to_children(tasklist, child.id);
+            for j in 0..tasklist.tasks.len() {
+                if tasklist.tasks[j].id == child.id {
+                    tasklist.tasks[j].start_time = dates.0;
+                    tasklist.tasks[j].due_date = dates.1;
+                }
+            }
+        }
+
+        if let Some(x) = dates.0
+            && (start_time.is_none() || x < start_time.unwrap())
+        {
+            start_time = Some(x);
+        }
+        if let Some(x) = dates.1
+            && (end_time.is_none() || x > end_time.unwrap())
+        {
+            end_time = Some(x);
+        }
+    }
+
+    for i in 0..tasklist.tasks.len() {
+        if tasklist.tasks[i].id == id {
+            tasklist.tasks[i].start_time = start_time;
+            tasklist.tasks[i].due_date = end_time;
+        }
+    }
+
+    (start_time, end_time)
+}
+
+/// A single field of a task as plain text. Missing due dates are empty and
+/// tags are joined with commas.
+pub fn task_field(task: &Task, field: TaskField, date_format: &str) -> String {
+    match field {
+        TaskField::Id => task.id.to_string(),
+        TaskField::Name => task.name.clone(),
+        TaskField::Points => task.points.to_string(),
+        TaskField::Due => match task.due_date {
+            Some(x) => x.format(date_format).to_string(),
+            None => "".to_string(),
+        },
+        TaskField::Complete => task.complete.to_string(),
+        TaskField::Tags => task.tags.join(","),
+        TaskField::Priority => task.priority.to_string(),
+    }
+}
+
+/// A table with the given fields of every task, under a header naming them.
+/// Columns are padded to line up, or separated by tabs when `plain`.
+pub fn render_columns(
+    tasklist: &TaskList,
+    columns: &[TaskField],
+    date_format: &str,
+    plain: bool,
+    name_width: Option<usize>,
+) -> String {
+    let mut rows: Vec<Vec<String>> = vec![];
+
+    rows.push(
+        columns
+            .iter()
+            .map(|x| x.to_possible_value().unwrap().get_name().to_string())
+            .collect(),
+    );
+
+    for task in tasklist.tasks.iter() {
+        rows.push(
+            columns
+                .iter()
+                .map(|x| match (x, name_width) {
+                    (TaskField::Name, Some(max)) => truncate_name(&task.name, max),
+                    _ => task_field(task, *x, date_format),
+                })
+                .collect(),
+        );
+    }
+
+    let mut out = String::new();
+
+    if plain {
+        for row in rows.iter() {
+            out += format!("{}\n", row.join("\t")).as_str();
+        }
+        return out;
+    }
+
+    let mut widths: Vec<usize> = vec![0; columns.len()];
+
+    for row in rows.iter() {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.width());
+        }
+    }
+
+    for row in rows.iter() {
+        let mut line = String::new();
+
+        for (i, cell) in row.iter().enumerate() {
+            line += cell;
+
+            if i + 1 < row.len() {
+                line += " ".repeat(widths[i] - cell.width() + 2).as_str();
+            }
+        }
+
+        out += format!("{line}\n").as_str();
+    }
+
+    out
+}
+
+/// Counts the tasks in each bucket, in bucket order. A task with several
+/// tags counts once for each of them.
+pub fn count_tasks_by(tasklist: &TaskList, by: CountBy) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut priorities: BTreeMap<u8, usize> = BTreeMap::new();
+
+    for task in tasklist.tasks.iter() {
+        match by {
+            CountBy::DueDay => {
+                let key = match task.due_date {
+                    Some(x) => x.format("%Y-%m-%d").to_string(),
+                    None => "(no due date)".to_string(),
+                };
+
+                *counts.entry(key).or_default() += 1;
+            }
+            CountBy::Tag => {
+                if task.tags.is_empty() {
+                    *counts.entry("(untagged)".to_string()).or_default() += 1;
+                }
+
+                for tag in task.tags.iter() {
+                    *counts.entry(tag.clone()).or_default() += 1;
+                }
+            }
+            CountBy::Priority => {
+                *priorities.entry(task.priority).or_default() += 1;
+            }
+        }
+    }
+
+    for (priority, count) in priorities {
+        counts.insert(format!("{priority:>3}"), count);
+    }
+
+    counts.into_iter().collect()
+}
+
+/// One line per bucket with a bar scaled so the largest bucket is 30 wide.
+pub fn render_histogram(buckets: &[(String, usize)]) -> String {
+    let mut out = String::new();
+
+    let label_width = buckets.iter().map(|x| x.0.width()).max().unwrap_or(0);
+    let max = buckets.iter().map(|x| x.1).max().unwrap_or(0).max(1);
+
+    for (label, count) in buckets.iter() {
+        let bar = "█".repeat((count * 30).div_ceil(max));
+
+        let padding = " ".repeat(label_width - label.width());
+
+        out += format!("{label}{padding} {bar} {count}\n").as_str();
+    }
+
+    out
+}
+
+/// Cuts `name` down to `max` terminal columns, ending it with `…` when
+/// shortened. Wide characters like CJK or emoji count as two columns.
+pub fn truncate_name(name: &str, max: usize) -> String {
+    if name.width() <= max {
+        return name.to_string();
+    }
+
+    if max == 0 {
+        return "…".to_string();
+    }
+
+    let mut short = String::new();
+    let mut used = 0;
+
+    for c in name.chars() {
+        let w = c.width().unwrap_or(0);
+
+        if used + w > max - 1 {
+            break;
+        }
+
+        short.push(c);
+        used += w;
+    }
+
+    short += "…";
+
+    short
+}
+
+/// How a single task should be highlighted: done, more than `grace`
+/// overdue, due soon or neither.
+pub fn task_status(task: &Task, now: DateTime<Local>, grace: TimeDelta) -> Option<Status> {
+    if task.complete {
+        return Some(Status::Good);
+    }
+
+    if task.is_overdue_after(now, grace) {
+        return Some(Status::Bad);
+    }
+
+    if task.is_due_within(now, TimeDelta::days(1)) {
+        return Some(Status::Warning);
+    }
+
+    None
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A standalone HTML page with a table of all tasks, highlighted the same
+/// way `list` highlights them but through CSS classes.
+pub fn generate_html(
+    tasklist: &TaskList,
+    now: DateTime<Local>,
+    grace: TimeDelta,
+    config: &Config,
+    date_format: &str,
+) -> String {
+    let mut html = String::new();
+
+    html += "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n";
+    html += "<title>Planner</title>\n<style>\n";
+    html += "body { font-family: sans-serif; margin: 2em; }\n";
+    html += "table { border-collapse: collapse; }\n";
+    html += "th, td { border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: left; }\n";
+    html += ".good { background: #d4f7d4; }\n";
+    html += ".warning { background: #fff3c4; }\n";
+    html += ".bad { background: #ffd4d4; }\n";
+    html += "</style>\n</head>\n<body>\n<h1>Planner</h1>\n<table>\n";
+    html += "<tr><th>Id</th><th>Task</th><th>Points</th><th>Due</th><th>Done</th></tr>\n";
+
+    let mut totpoints: u64 = 0;
+    let mut allpoints: u64 = 0;
+
+    for task in tasklist.tasks.iter() {
+        allpoints += task.points as u64;
+        if task.complete {
+            totpoints += task.total_points();
+        }
+
+        let class = match task_status(task, now, grace) {
+            Some(Status::Good) => " class=\"good\"",
+            Some(Status::Warning) => " class=\"warning\"",
+            Some(Status::Bad) => " class=\"bad\"",
+            None => "",
+        };
+
+        let due = match task.due_date {
+            Some(x) => x.format(date_format).to_string(),
+            None => "".to_string(),
+        };
+
+        html += format!(
+            "<tr{class}><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}%</td></tr>\n",
+            task.id,
+            escape_html(&task.name),
+            task.points,
+            escape_html(&due),
+            task_progress(task)
+        )
+        .as_str();
+    }
+
+    let perc = completion_percentage(totpoints, allpoints);
+    let class = match summary_status(perc, config) {
+        Status::Good => "good",
+        Status::Warning => "warning",
+        Status::Bad => "bad",
+    };
+
+    html += "</table>\n";
+    html += format!("<p class=\"{class}\">Total points: {totpoints} ({perc}%)</p>\n").as_str();
+    html += "</body>\n</html>\n";
+
+    html
+}
+
+/// Renders the list as one character per task, `▣` when done, `!` when
+/// overdue and `□` otherwise, followed by the completion percentage. Tasks
+/// that don't fit in `width` columns are cut off with `…`.
+pub fn compact_line(
+    tasklist: &TaskList,
+    now: DateTime<Local>,
+    grace: TimeDelta,
+    scheme: ColorScheme,
+    width: usize,
+) -> String {
+    let mut line = String::new();
+
+    let mut totpoints: u64 = 0;
+    let mut allpoints: u64 = 0;
+
+    // Leave room for " 100%"
+    let budget = width.saturating_sub(5).max(1);
+    let truncated = tasklist.tasks.len() > budget;
+
+    for (i, task) in tasklist.tasks.iter().enumerate() {
+        allpoints += task.points as u64;
+        if task.complete {
+            totpoints += task.total_points();
+        }
+
+        if truncated && i + 1 >= budget {
+            if i + 1 == budget {
+                line += "…";
+            }
+            continue;
+        }
+
+        let (c, status) = if task.complete {
+            ("▣", Some(Status::Good))
+        } else if task.is_overdue_after(now, grace) {
+            ("!", Some(Status::Bad))
+        } else {
+            ("□", None)
+        };
+
+        match status {
+            Some(x) if scheme != ColorScheme::Mono => line += colorize(c, x, scheme).as_str(),
+            _ => line += c,
+        }
+    }
+
+    line += format!(" {}%", completion_percentage(totpoints, allpoints)).as_str();
+
+    line
+}
+
+/// Scores how pressing a task is: its points divided by the days left until
+/// it is due, growing with every day once it is overdue. Completed tasks
+/// and tasks without a due date score 0.
+pub fn urgency_score(task: &Task, now: DateTime<Local>) -> f64 {
+    if task.complete {
+        return 0.0;
+    }
+
+    let Some(left) = task.time_left(now) else {
+        return 0.0;
+    };
+
+    let points = task.points.max(1) as f64;
+    let days_left = left.num_seconds() as f64 / 86400.0;
+
+    if days_left < 0.0 {
+        return points * (2.0 - days_left);
+    }
+
+    points / (1.0 + days_left)
+}
+
+/// Orders two tasks by a single `list --sort` key.
+pub fn compare_tasks(a: &Task, b: &Task, key: SortKey, now: DateTime<Local>) -> Ordering {
+    match key {
+        SortKey::Id => a.id.cmp(&b.id),
+        SortKey::Name => a.name.cmp(&b.name),
+        SortKey::Points => b.points.cmp(&a.points),
+        SortKey::Priority => b.priority.cmp(&a.priority),
+        SortKey::Due => match (a.due_date, b.due_date) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        },
+        SortKey::Urgency => urgency_score(b, now).total_cmp(&urgency_score(a, now)),
+        SortKey::Smart => match (a.complete, b.complete) {
+            (false, false) => compare_tasks(a, b, SortKey::Due, now),
+            (false, true) => Ordering::Less,
+            (true, false) => Ordering::Greater,
+            (true, true) => a.completed_at.cmp(&b.completed_at),
+        },
+    }
+}
+
+/// Orders tasks by `key`, breaking ties with `secondary` if given and then
+/// by their stored order.
+pub fn sort_tasks(
+    tasks: &mut [Task],
+    key: SortKey,
+    secondary: Option<SortKey>,
+    now: DateTime<Local>,
+) {
+    tasks.sort_by(|a, b| {
+        compare_tasks(a, b, key, now).then_with(|| match secondary {
+            Some(x) => compare_tasks(a, b, x, now),
+            None => Ordering::Equal,
+        })
+    });
+}
+
+/// Values derived from a task at a given time, for JSON consumers that
+/// don't want to work them out themselves.
+#[derive(Serialize, Debug, Clone)]
+pub struct ComputedFields {
+    pub time_left_seconds: Option<i64>,
+    pub is_overdue: bool,
+    pub urgency_score: f64,
+}
+
+/// A task serialized with its stored fields plus a `computed` object.
+#[derive(Serialize, Debug, Clone)]
+pub struct TaskWithComputed<'a> {
+    #[serde(flatten)]
+    pub task: &'a Task,
+    pub computed: ComputedFields,
+}
+
+/// What `list --json` prints around the tasks. The keys are stable:
+/// `list_name` is the list file's name without `.json`, `version` the
+/// `SCHEMA_VERSION` the tasks follow and `generated_at` a UTC timestamp,
+/// only there when asked for as it changes on every run.
+#[derive(Serialize)]
+pub struct ListEnvelope<'a, T: Serialize> {
+    pub list_name: String,
+    pub version: u32,
+    #[serde(with = "utc_time_opt", skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<DateTime<Local>>,
+    pub tasks: &'a [T],
+}
+
+/// How `list --json` prints the tasks.
+pub struct JsonOptions {
+    /// Only the array of tasks, without the `ListEnvelope`
+    pub bare: bool,
+    pub pretty: bool,
+    /// Add each task's `ComputedFields`, counting it overdue once it's more
+    /// than this past due
+    pub computed_grace: Option<TimeDelta>,
+    /// Stamp the envelope with `generated_at`
+    pub timestamp: bool,
+}
+
+/// Serializes the tasks for `list --json`. They are always sorted by id, so
+/// the output doesn't depend on how tasks are stored and diffs between runs
+/// stay small.
+pub fn list_json(
+    tasklist: &TaskList,
+    list_name: &str,
+    opts: &JsonOptions,
+    now: DateTime<Local>,
+) -> String {
+    let mut sorted = tasklist.clone();
+    sorted.tasks.sort_by_key(|x| x.id);
+
+    match opts.computed_grace {
+        Some(grace) => envelope_json(&with_computed(&sorted, now, grace), list_name, opts, now),
+        None => envelope_json(&sorted.tasks, list_name, opts, now),
+    }
+}
+
+fn envelope_json<T: Serialize>(
+    tasks: &[T],
+    list_name: &str,
+    opts: &JsonOptions,
+    now: DateTime<Local>,
+) -> String {
+    let envelope = ListEnvelope {
+        list_name: list_name.to_string(),
+        version: SCHEMA_VERSION,
+        generated_at: opts.timestamp.then_some(now),
+        tasks,
+    };
+
+    let json = match (opts.bare, opts.pretty) {
+        (false, false) => serde_json::to_string(&envelope),
+        (false, true) => serde_json::to_string_pretty(&envelope),
+        (true, false) => serde_json::to_string(tasks),
+        (true, true) => serde_json::to_string_pretty(tasks),
+    };
+
+    json.unwrap()
+}
+
+/// Works out the computed fields of `task` at `now`, counting it overdue
+/// only once it's more than `grace` past due.
+pub fn computed_fields(task: &Task, now: DateTime<Local>, grace: TimeDelta) -> ComputedFields {
+    ComputedFields {
+        time_left_seconds: task.time_left(now).map(|x| x.num_seconds()),
+        is_overdue: task.is_overdue_after(now, grace),
+        urgency_score: urgency_score(task, now),
+    }
+}
+
+/// Pairs every task of the list with its computed fields.
+pub fn with_computed(
+    tasklist: &TaskList,
+    now: DateTime<Local>,
+    grace: TimeDelta,
+) -> Vec<TaskWithComputed<'_>> {
+    tasklist
+        .tasks
+        .iter()
+        .map(|x| TaskWithComputed {
+            task: x,
+            computed: computed_fields(x, now, grace),
+        })
+        .collect()
+}
+
+/// Flags changing how a single task is rendered by `render_task`.
+#[derive(Debug, Clone, Default)]
+pub struct DisplayOptions {
+    pub urgency: bool,
+    pub plain: bool,
+    pub scheme: ColorScheme,
+    pub relative_dates: bool,
+    pub date_format: String,
+    /// Shorten names so the first line of each task fits this many columns
+    pub width: Option<usize>,
+    /// Shorten names longer than this many columns
+    pub name_width: Option<usize>,
+    /// ANSI codes for tags, from `resolve_tag_colors`
+    pub tag_colors: BTreeMap<String, String>,
+    pub color_lines: bool,
+    /// Mark tasks completed at or after this time
+    pub completed_since: Option<DateTime<Local>>,
+}
+
+/// Describes a duration in its largest whole unit, e.g. "3 days".
+pub fn humanize_duration(duration: TimeDelta) -> String {
+    let secs = duration.num_seconds().abs();
+
+    let (amount, unit) = if secs >= 7 * 86400 {
+        (secs / (7 * 86400), "week")
+    } else if secs >= 86400 {
+        (secs / 86400, "day")
+    } else if secs >= 3600 {
+        (secs / 3600, "hour")
+    } else if secs >= 60 {
+        (secs / 60, "minute")
+    } else {
+        (secs, "second")
+    };
+
+    if amount == 1 {
+        return format!("1 {unit}");
+    }
+
+    format!("{amount} {unit}s")
+}
+
+/// Formats a date for a task line, either as "on <timestamp>" or relative
+/// to now as "in 3 days" / "3 days ago".
+pub fn format_task_date(date: DateTime<Local>, opts: &DisplayOptions) -> String {
+    if !opts.relative_dates {
+        return format!("on {}", date.format(&opts.date_format));
+    }
+
+    let diff = date - Local::now();
+
+    if diff < TimeDelta::zero() {
+        return format!("{} ago", humanize_duration(diff));
+    }
+
+    format!("in {}", humanize_duration(diff))
+}
+
+/// Turns the config's tag colors into ANSI codes, skipping names that
+/// aren't colors. Empty when the scheme has no colors.
+pub fn resolve_tag_colors(config: &Config, scheme: ColorScheme) -> BTreeMap<String, String> {
+    let mut colors: BTreeMap<String, String> = BTreeMap::new();
+
+    if scheme == ColorScheme::Off || scheme == ColorScheme::Mono {
+        return colors;
+    }
+
+    for (tag, name) in config.tag_colors.iter() {
+        if let Some(x) = color_code(name) {
+            colors.insert(tag.clone(), x);
+        }
+    }
+
+    colors
+}
+
+/// Appends the lines showing a single task to `out`.
+pub fn render_task(i: &Task, indent: u8, opts: &DisplayOptions, out: &mut String) {
+    for _i in 0..indent {
+        *out += "  ";
+    }
+
+    let mut msg = format!(" ({} points)", i.points);
+
+    if i.bonus_points > 0 && i.penalty_points > 0 {
+        msg = format!(
+            " ({} points +{} bonus -{} late)",
+            i.points, i.bonus_points, i.penalty_points
+        );
+    } else if i.bonus_points > 0 {
+        msg = format!(" ({} points +{} bonus)", i.points, i.bonus_points);
+    } else if i.penalty_points > 0 {
+        msg = format!(" ({} points -{} late)", i.points, i.penalty_points);
+    }
+
+    match (i.start_time, i.due_date) {
+        (Some(start), None) => {
+            msg += format!("  Start work {}", format_task_date(start, opts)).as_str();
+        }
+        (None, Some(due)) => {
+            msg += format!("  Due {}", format_task_date(due, opts)).as_str();
+        }
+        (Some(start), Some(due)) => {
+            msg += format!(
+                "  Start work {} and end {}",
+                format_task_date(start, opts),
+                format_task_date(due, opts)
+            )
+            .as_str();
+        }
+        (None, None) => {}
+    }
+
+    for tag in i.tags.iter() {
+        msg += format!(" [{tag}]").as_str();
+    }
+
+    if let Some(x) = i.progress
+        && !i.complete
+    {
+        msg += format!("  {} {x}%", progress_bar(x)).as_str();
+    }
+
+    if let Some(x) = &i.recurrence {
+        let every = match x.every() {
+            Ok(every) => humanize_duration(every),
+            Err(_) => format!("{} seconds", x.every_seconds),
+        };
+
+        msg += format!("  Repeats every {every}").as_str();
+    }
+
+    if opts.urgency {
+        msg += format!("  [urgency {:.1}]", urgency_score(i, Local::now())).as_str();
+    }
+
+    if let (Some(since), Some(at)) = (opts.completed_since, i.completed_at)
+        && i.complete
+        && at >= since
+    {
+        msg += format!("  [completed {}]", format_task_date(at, opts)).as_str();
+    }
+
+    let head = format!("#{} ", i.id);
+    let mut name = i.name.clone();
+
+    if let Some(max) = opts.name_width {
+        name = truncate_name(&name, max);
+    }
+
+    if let Some(width) = opts.width {
+        let used = indent as usize * 2 + head.width() + msg.width();
+
+        name = truncate_name(&name, width.saturating_sub(used));
+    }
+
+    let line_color = i.tags.iter().find_map(|x| opts.tag_colors.get(x));
+
+    if !opts.plain && !opts.color_lines {
+        for tag in i.tags.iter() {
+            if let Some(code) = opts.tag_colors.get(tag) {
+                let label = format!("[{tag}]");
+                msg = msg.replacen(&label, &paint(&label, code), 1);
+            }
+        }
+    }
+
+    msg = format!("{head}{name}{msg}");
+
+    if let Some(code) = line_color
+        && !opts.plain
+        && opts.color_lines
+        && !i.complete
+    {
+        msg = paint(&msg, code);
+    }
+
+    if opts.plain {
+        *out += format!("{msg}\n").as_str();
+        return;
+    }
+
+    if !i.resources.is_empty() {
+        msg += "\n";
+        for _i in 0..indent {
+            msg += "  ";
+        }
+
+        msg += format!("Required resources: {:?}", i.resources).as_str();
+    }
+
+    if let Some(note) = &i.completion_note {
+        msg += "\n";
+        for _i in 0..indent {
+            msg += "  ";
+        }
+
+        msg += format!("Note: {note}").as_str();
+    }
+
+    if i.complete {
+        msg = colorize(&msg, Status::Good, opts.scheme);
+    }
+
+    *out += format!("{msg}\n").as_str();
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskTreeNode {
+    pub task: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+/// The ids of the tasks in the order `list` shows them, subtasks right
+/// after their parent.
+pub fn displayed_order(tasklist: &TaskList) -> Vec<usize> {
+    let tree = generate_task_tree(tasklist);
+
+    let mut order: Vec<usize> = vec![];
+    let mut stack: Vec<usize> = vec![0];
+
+    while let Some(idx) = stack.pop() {
+        if let Some(x) = tree[idx].task {
+            order.push(x);
+        }
+
+        for child in tree[idx].children.iter().rev() {
+            stack.push(*child);
+        }
+    }
+
+    order
+}
+
+/// Which tasks a command working on several at once applies to, like
+/// `rm --completed --tag work`. Every condition that is set has to hold.
+#[derive(Debug, Clone, Default)]
+pub struct TaskFilter {
+    pub completed: bool,
+    pub overdue: bool,
+    pub tag: Option<String>,
+}
+
+impl TaskFilter {
+    /// Whether `task` passes, counting it overdue only once it is more than
+    /// `grace` past due.
+    pub fn matches(&self, task: &Task, now: DateTime<Local>, grace: TimeDelta) -> bool {
+        (!self.completed || task.complete)
+            && (!self.overdue || task.is_overdue_after(now, grace))
+            && self.tag.as_ref().is_none_or(|x| task.tags.contains(x))
+    }
+}
+
+/// The tasks for which `keep` is true. Tasks whose parent is left out are
+/// shown at the top level instead.
+pub fn filter_tasks(tasklist: &TaskList, keep: impl Fn(&Task) -> bool) -> TaskList {
+    let mut filtered = TaskList {
+        tasks: tasklist.tasks.iter().filter(|x| keep(x)).cloned().collect(),
+        ..tasklist.clone()
+    };
+
+    let ids: Vec<usize> = filtered.tasks.iter().map(|x| x.id).collect();
+
+    for task in filtered.tasks.iter_mut() {
+        if task.parent.is_some_and(|x| !ids.contains(&x)) {
+            task.parent = None;
+        }
+    }
+
+    filtered
+}
+
+pub fn generate_task_tree(tasklist: &TaskList) -> Vec<TaskTreeNode> {
+    let mut tree = vec![TaskTreeNode {
+        task: None,
+        children: vec![],
+    }];
+
+    let mut queue: Vec<usize> = (0..tasklist.tasks.len()).collect();
+
+    loop {
+        if queue.is_empty() {
+            break;
+        }
+
+        let t = queue.pop().unwrap();
+
+        let mut proc = false;
+
+        for i in 0..tree.len() {
+            if tree[i].task == tasklist.tasks[t].parent {
+                let l = tree.len();
+
+                tree[i].children.push(l);
+                tree.push(TaskTreeNode {
+                    task: Some(tasklist.tasks[t].id),
+                    children: vec![],
+                });
+
+                proc = true;
+                break;
+            }
+        }
+
+        if !proc {
+            queue.insert(0, t);
+        }
+    }
+
+    tree
+}
+
+pub fn render_task_tree(
+    tasklist: &TaskList,
+    tree: Vec<TaskTreeNode>,
+    depth: u8,
+    idx: usize,
+    opts: &DisplayOptions,
+    out: &mut String,
+) {
+    let r = tree[idx].clone();
+
+    let mut depth_add = 0;
+
+    if let Some(x) = r.task {
+        if let Some(t) = get_task_index(tasklist, x) {
+            render_task(&tasklist.tasks[t], depth, opts, out);
+        }
+        depth_add = 1;
+    }
+
+    if !r.children.is_empty() {
+        for i in r.children {
+            render_task_tree(tasklist, tree.clone(), depth + depth_add, i, opts, out);
+        }
+    }
+}
+
+/// The ids of the tasks sharing a parent with `id`, in the order `list`
+/// shows them.
+pub fn get_displayed_siblings(tasklist: &TaskList, id: usize) -> Vec<usize> {
+    let tree = generate_task_tree(tasklist);
+
+    for node in tree.iter() {
+        let siblings: Vec<usize> = node.children.iter().filter_map(|x| tree[*x].task).collect();
+
+        if siblings.contains(&id) {
+            return siblings;
+        }
+    }
+
+    vec![]
+}
+
+/// Swaps a task with its previous (`up`) or next displayed sibling by
+/// exchanging their places in the stored list, which is what the display
+/// order follows. Returns the id of the sibling it was swapped with.
+pub fn move_task(tasklist: &mut TaskList, id: usize, up: bool) -> Option<usize> {
+    let siblings = get_displayed_siblings(tasklist, id);
+    let pos = siblings.iter().position(|x| *x == id)?;
+
+    let neighbor = if up {
+        *siblings.get(pos.checked_sub(1)?)?
+    } else {
+        *siblings.get(pos + 1)?
+    };
+
+    let a = get_task_index(tasklist, id)?;
+    let b = get_task_index(tasklist, neighbor)?;
+
+    tasklist.tasks.swap(a, b);
+
+    Some(neighbor)
+}
+
+pub fn get_dependents_of_task(tasklist: &TaskList, id: usize) -> Vec<usize> {
+    let mut dependents: Vec<usize> = vec![];
+
+    for i in 0..tasklist.tasks.len() {
+        if tasklist.tasks[i].depends_on.contains(&id) {
+            dependents.push(tasklist.tasks[i].id);
+        }
+    }
+
+    dependents
+}
+
+/// The dependency links that removing the tasks in `removed` would break, as
+/// pairs of the dependent task's id and the removed task's id. Dependents
+/// that are removed too aren't counted.
+pub fn broken_dependencies(tasklist: &TaskList, removed: &[usize]) -> Vec<(usize, usize)> {
+    let mut broken: Vec<(usize, usize)> = vec![];
+
+    for id in removed.iter() {
+        for dependent in get_dependents_of_task(tasklist, *id) {
+            if !removed.contains(&dependent) {
+                broken.push((dependent, *id));
+            }
+        }
+    }
+
+    broken
+}
+
+/// Checks off, starting from the just completed `id`, every parent whose
+/// subtasks are now all complete and every dependent whose dependencies are,
+/// repeating for each task completed this way. Returns their ids in order.
+pub fn cascade_completions(tasklist: &mut TaskList, id: usize, at: DateTime<Local>) -> Vec<usize> {
+    let mut completed: Vec<usize> = vec![];
+    let mut visited: Vec<usize> = vec![id];
+    let mut queue: Vec<usize> = vec![id];
+
+    while let Some(current) = queue.pop() {
+        let Some(idx) = get_task_index(tasklist, current) else {
+            continue;
+        };
+
+        let mut candidates = get_dependents_of_task(tasklist, current);
+
+        if let Some(x) = tasklist.tasks[idx].parent {
+            candidates.push(x);
+        }
+
+        for candidate in candidates {
+            if visited.contains(&candidate) {
+                continue;
+            }
+
+            let Some(cidx) = get_task_index(tasklist, candidate) else {
+                continue;
+            };
+
+            let task = &tasklist.tasks[cidx];
+
+            if task.complete {
+                continue;
+            }
+
+            let deps_done = task.depends_on.iter().all(|dep| {
+                get_task_index(tasklist, *dep).is_none_or(|x| tasklist.tasks[x].complete)
+            });
+
+            if !deps_done {
+                continue;
+            }
+
+            // Fails while subtasks are still open
+            if complete_task(tasklist, candidate, at, None).is_ok() {
+                visited.push(candidate);
+                completed.push(candidate);
+                queue.push(candidate);
+            }
+        }
+    }
+
+    completed
+}
+
+/// Checks off every open task `id` depends on, directly or through other
+/// dependencies, each after its own dependencies. Returns the ids checked
+/// off in order, then those left open because they still wait on open
+/// subtasks or dependencies.
+pub fn close_dependencies(
+    tasklist: &mut TaskList,
+    id: usize,
+    at: DateTime<Local>,
+) -> (Vec<usize>, Vec<usize>) {
+    let mut order: Vec<usize> = vec![];
+    let mut visited: Vec<usize> = vec![];
+    let mut stack: Vec<(usize, bool)> = vec![(id, false)];
+
+    // Depth first, adding a task only once all its dependencies are added.
+    // Tasks are marked when expanded rather than when pushed, so one reached
+    // again through another path is still added after its own dependencies,
+    // and cycles end as tasks being expanded are skipped.
+    while let Some((current, expanded)) = stack.pop() {
+        if expanded {
+            if current != id {
+                order.push(current);
+            }
+            continue;
+        }
+
+        if visited.contains(&current) {
+            continue;
+        }
+
+        visited.push(current);
+        stack.push((current, true));
+
+        let Some(idx) = get_task_index(tasklist, current) else {
+            continue;
+        };
+
+        for dep in tasklist.tasks[idx].depends_on.iter() {
+            if visited.contains(dep) {
+                continue;
+            }
+
+            let open = get_task_index(tasklist, *dep).is_some_and(|x| !tasklist.tasks[x].complete);
+
+            if open {
+                stack.push((*dep, false));
+            }
+        }
+    }
+
+    let mut closed: Vec<usize> = vec![];
+    let mut skipped: Vec<usize> = vec![];
+
+    for dep in order {
+        let idx = get_task_index(tasklist, dep).unwrap();
+
+        // `id` is checked off right after, so a cycle back to it doesn't block
+        let blocked = tasklist.tasks[idx].depends_on.iter().any(|x| {
+            *x != id && get_task_index(tasklist, *x).is_some_and(|y| !tasklist.tasks[y].complete)
+        });
+
+        if blocked {
+            skipped.push(dep);
+            continue;
+        }
+
+        // Fails while subtasks are still open
+        match complete_task(tasklist, dep, at, None) {
+            Ok(_) => closed.push(dep),
+            Err(_) => skipped.push(dep),
+        }
+    }
+
+    (closed, skipped)
+}
+
+/// Renders `id` and, indented below it, every task depending on it. `path`
+/// holds the ids above this node so a dependency cycle is cut off instead
+/// of recursing forever.
+pub fn render_dependency_tree(
+    tasklist: &TaskList,
+    id: usize,
+    depth: u8,
+    path: &mut Vec<usize>,
+    printed: &mut Vec<usize>,
+    opts: &DisplayOptions,
+    out: &mut String,
+) {
+    let Some(idx) = get_task_index(tasklist, id) else {
+        return;
+    };
+
+    render_task(&tasklist.tasks[idx], depth, opts, out);
+
+    if !printed.contains(&id) {
+        printed.push(id);
+    }
+
+    if path.contains(&id) {
+        return;
+    }
+
+    path.push(id);
+
+    for dependent in get_dependents_of_task(tasklist, id) {
+        if !path.contains(&dependent) {
+            render_dependency_tree(tasklist, dependent, depth + 1, path, printed, opts, out);
+        }
+    }
+
+    path.pop();
+}
+
+pub fn render_dependency_forest(
+    tasklist: &TaskList,
+    depth: u8,
+    opts: &DisplayOptions,
+    out: &mut String,
+) {
+    let mut printed: Vec<usize> = vec![];
+
+    for i in 0..tasklist.tasks.len() {
+        let mut is_root = true;
+
+        for dep in tasklist.tasks[i].depends_on.iter() {
+            if get_task_index(tasklist, *dep).is_some() {
+                is_root = false;
+                break;
+            }
+        }
+
+        if is_root {
+            render_dependency_tree(
+                tasklist,
+                tasklist.tasks[i].id,
+                depth,
+                &mut vec![],
+                &mut printed,
+                opts,
+                out,
+            );
+        }
+    }
+
+    // Tasks that are only reachable through a cycle have no root, print
+    // each remaining cycle starting from its first task
+    for i in 0..tasklist.tasks.len() {
+        if !printed.contains(&tasklist.tasks[i].id) {
+            render_dependency_tree(
+                tasklist,
+                tasklist.tasks[i].id,
+                depth,
+                &mut vec![],
+                &mut printed,
+                opts,
+                out,
+            );
+        }
+    }
+}
+
+/// The first instant of the day, week (starting on `week_start`) or month
+/// containing `now`.
+pub fn get_period_start(
+    period: Period,
+    now: DateTime<Local>,
+    week_start: WeekStart,
+) -> DateTime<Local> {
+    let today = now.date_naive();
+
+    let first_day = match period {
+        Period::Day => today,
+        Period::Week => today - Days::new(today.weekday().days_since(week_start.weekday()) as u64),
+        Period::Month => today.with_day(1).unwrap(),
+    };
+
+    start_of_day(first_day)
+}
+
+/// Midnight of `day`, or when a daylight saving change skips midnight, the
+/// instant the clocks go forward.
+fn start_of_day(day: NaiveDate) -> DateTime<Local> {
+    let midnight = day.and_time(NaiveTime::MIN);
+
+    if let Some(x) = Local.from_local_datetime(&midnight).earliest() {
+        return x;
+    }
+
+    // The change is within a day of midnight, so a day before is still in
+    // the old offset, and midnight in that offset is when the change happens
+    let offset = Local.offset_from_utc_datetime(&(midnight - TimeDelta::days(1)));
+
+    Local.from_utc_datetime(&(midnight - TimeDelta::seconds(offset.local_minus_utc() as i64)))
+}
+
+/// The points of the tasks completed at or after `since`, with their bonus.
+pub fn points_earned_since(tasklist: &TaskList, since: DateTime<Local>) -> u64 {
+    let mut earned: u64 = 0;
+
+    for task in tasklist.tasks.iter() {
+        if task.complete && task.completed_at.is_some_and(|x| x >= since) {
+            earned += task.total_points();
+        }
+    }
+
+    earned
+}
+
+pub fn generate_report(
+    tasklist: &TaskList,
+    period: Period,
+    now: DateTime<Local>,
+    grace: TimeDelta,
+    week_start: WeekStart,
+    date_format: &str,
+) -> String {
+    let start = get_period_start(period, now, week_start);
+
+    let period_name = match period {
+        Period::Day => "day",
+        Period::Week => "week",
+        Period::Month => "month",
+    };
+
+    let mut completed: Vec<&Task> = vec![];
+    let mut open: Vec<&Task> = vec![];
+    let mut overdue: Vec<&Task> = vec![];
+    let mut earned: u64 = 0;
+
+    for task in tasklist.tasks.iter() {
+        if task.complete {
+            if let Some(x) = task.completed_at
+                && x >= start
+                && x <= now
+            {
+                completed.push(task);
+                earned += task.total_points();
+            }
+        } else {
+            open.push(task);
+
+            if task.is_overdue_after(now, grace) {
+                overdue.push(task);
+            }
+        }
+    }
+
+    let mut report = format!(
+        "# Report for the {period_name} from {} to {}\n\n",
+        start.format("%Y-%m-%d"),
+        now.format("%Y-%m-%d")
+    );
+
+    report += format!("Points earned: {earned}\n\n").as_str();
+
+    report += format!("## Completed ({})\n\n", completed.len()).as_str();
+    if completed.is_empty() {
+        report += format!("No tasks completed this {period_name}.\n").as_str();
+    }
+    for task in completed {
+        report += format!(
+            "- {} ({} points), completed on {}\n",
+            task.name,
+            task.points,
+            task.completed_at.unwrap().format(date_format)
+        )
+        .as_str();
+    }
+
+    report += format!("\n## Open ({})\n\n", open.len()).as_str();
+    if open.is_empty() {
+        report += "No open tasks.\n";
+    }
+    for task in open {
+        report += format!("- {} ({} points)\n", task.name, task.points).as_str();
+    }
+
+    report += format!("\n## Overdue ({})\n\n", overdue.len()).as_str();
+    if overdue.is_empty() {
+        report += "No overdue tasks.\n";
+    }
+    for task in overdue {
+        report += format!(
+            "- {} ({} points), due on {}\n",
+            task.name,
+            task.points,
+            task.due_date.unwrap().format(date_format)
+        )
+        .as_str();
+    }
+
+    report
+}
+
+/// The longest reminder of `task` whose window `now` is in, that is after
+/// `due - lead` but before the due date itself.
+pub fn active_reminder(task: &Task, now: DateTime<Local>) -> Option<TimeDelta> {
+    if task.complete {
+        return None;
+    }
+
+    let left = task.time_left(now)?;
+
+    if left <= TimeDelta::zero() {
+        return None;
+    }
+
+    task.reminders
+        .iter()
+        .filter(|lead| **lead >= left)
+        .max()
+        .copied()
+}
+
+/// Describes what changed from `old` to `new`, a line per change, matching
+/// tasks by id: added, removed, completed or reopened tasks, and the fields
+/// changed on the others.
+pub fn render_task_list_diff(old: &TaskList, new: &TaskList, scheme: ColorScheme) -> String {
+    let mut out = String::new();
+    let mut changes = 0;
+
+    for task in old.tasks.iter() {
+        if get_task_index(new, task.id).is_none() {
+            let msg = format!("- #{} {}", task.id, task.name);
+            out += format!("{}\n", colorize(&msg, Status::Bad, scheme)).as_str();
+            changes += 1;
+        }
+    }
+
+    for task in new.tasks.iter() {
+        let Some(idx) = get_task_index(old, task.id) else {
+            let msg = format!("+ #{} {}", task.id, task.name);
+            out += format!("{}\n", colorize(&msg, Status::Good, scheme)).as_str();
+            changes += 1;
+            continue;
+        };
+
+        let before = &old.tasks[idx];
+
+        if !before.complete && task.complete {
+            let msg = format!("~ #{} {} completed", task.id, task.name);
+            out += format!("{}\n", colorize(&msg, Status::Good, scheme)).as_str();
+            changes += 1;
+        } else if before.complete && !task.complete {
+            let msg = format!("~ #{} {} reopened", task.id, task.name);
+            out += format!("{}\n", colorize(&msg, Status::Warning, scheme)).as_str();
+            changes += 1;
+        }
+
+        let serde_json::Value::Object(before) = serde_json::to_value(before).unwrap() else {
+            continue;
+        };
+        let serde_json::Value::Object(after) = serde_json::to_value(task).unwrap() else {
+            continue;
+        };
+
+        let ignored = ["complete", "completed_at", "completion_note"];
+
+        let changed: Vec<&String> = after
+            .keys()
+            .filter(|k| !ignored.contains(&k.as_str()) && before.get(*k) != after.get(*k))
+            .collect();
+
+        if !changed.is_empty() {
+            let fields: Vec<&str> = changed.iter().map(|x| x.as_str()).collect();
+            let msg = format!("~ #{} {} changed {}", task.id, task.name, fields.join(", "));
+            out += format!("{}\n", colorize(&msg, Status::Warning, scheme)).as_str();
+            changes += 1;
+        }
+    }
+
+    if changes == 0 {
+        out += "No changes\n";
+    }
+
+    out
+}
+
+/// Counts the open tasks due before the end of `now`'s day, overdue ones included.
+pub fn count_due_today(tasklist: &TaskList, now: DateTime<Local>) -> usize {
+    let tomorrow = (now.date_naive() + Days::new(1))
+        .and_time(NaiveTime::MIN)
+        .and_local_timezone(Local)
+        .earliest();
+
+    let Some(end) = tomorrow else {
+        return 0;
+    };
+
+    tasklist
+        .tasks
+        .iter()
+        .filter(|x| x.is_due_within(now, end - now))
+        .count()
+}
+
+/// Takes a task out of the list, moving its subtasks to the top level.
+pub fn remove_task(tasklist: &mut TaskList, id: usize) -> Result<Task, PlannerError> {
+    let Some(idx) = get_task_index(tasklist, id) else {
+        return Err(PlannerError::TaskNotFound(id));
+    };
+
+    for i in 0..tasklist.tasks.len() {
+        if tasklist.tasks[i].parent == Some(id) {
+            tasklist.tasks[i].parent = None;
+        }
+    }
+
+    Ok(tasklist.tasks.remove(idx))
+}
+
+/// Clears every parent and dependency link pointing at `id`.
+pub fn remove_references_to(tasklist: &mut TaskList, id: usize) {
+    for task in tasklist.tasks.iter_mut() {
+        if task.parent == Some(id) {
+            task.parent = None;
+        }
+        task.depends_on.retain(|x| *x != id);
+    }
+}
+
+/// Removes the tasks in `ids` and every link to them. Unless `force`, fails
+/// without removing anything while another task depends on one of them, see
+/// `broken_dependencies`.
+pub fn remove_tasks(
+    tasklist: &mut TaskList,
+    ids: &[usize],
+    force: bool,
+) -> Result<Vec<Task>, PlannerError> {
+    for id in ids.iter() {
+        if get_task_index(tasklist, *id).is_none() {
+            return Err(PlannerError::TaskNotFound(*id));
+        }
+    }
+
+    if let Some((_, id)) = broken_dependencies(tasklist, ids).first()
+        && !force
+    {
+        let idx = get_task_index(tasklist, *id).unwrap();
+
+        return Err(PlannerError::Generic(format!(
+            "Other tasks depend on '{}'",
+            tasklist.tasks[idx].name
+        )));
+    }
+
+    let mut removed: Vec<Task> = vec![];
+
+    for id in ids.iter() {
+        removed.push(remove_task(tasklist, *id)?);
+        remove_references_to(tasklist, *id);
+    }
+
+    Ok(removed)
+}
+
+/// Moves the task `id` to the end of another list under a free id there.
+/// Its links and the links to it are dropped, as they only make sense
+/// inside the source list. Returns the task as added.
+pub fn move_task_to_list(
+    from: &mut TaskList,
+    to: &mut TaskList,
+    id: usize,
+) -> Result<Task, PlannerError> {
+    let Some(idx) = get_task_index(from, id) else {
+        return Err(PlannerError::TaskNotFound(id));
+    };
+
+    let mut task = from.tasks.remove(idx);
+    remove_references_to(from, id);
+
+    task.id = get_free_id(to);
+    task.parent = None;
+    task.depends_on = vec![];
+
+    to.tasks.push(task.clone());
+
+    Ok(task)
+}
+
+/// Moves the due date and start time of the tasks in `ids` later by `delay`.
+pub fn snooze_tasks(tasklist: &mut TaskList, ids: &[usize], delay: TimeDelta) {
+    for task in tasklist.tasks.iter_mut() {
+        if ids.contains(&task.id) {
+            task.due_date = task.due_date.map(|x| x + delay);
+            task.start_time = task.start_time.map(|x| x + delay);
+        }
+    }
+}
+
+/// Adds `delta` to the points of a task, or subtracts it when negative,
+/// keeping them between 0 and the configured maximum and on the points
+/// scheme. Returns the points before and after.
+pub fn bump_points(
+    tasklist: &mut TaskList,
+    id: usize,
+    delta: i64,
+    config: &Config,
+) -> Result<(u32, u32), PlannerError> {
+    let Some(idx) = get_task_index(tasklist, id) else {
+        return Err(PlannerError::TaskNotFound(id));
+    };
+
+    let max = config.max_points.unwrap_or(u32::MAX);
+    let old = tasklist.tasks[idx].points;
+    let new = (old as i64).saturating_add(delta).clamp(0, max as i64) as u32;
+    let new = snap_points(new, config.points_scheme);
+
+    tasklist.tasks[idx].points = new;
+
+    Ok((old, new))
+}
+
+/// Moves tasks completed more than `max_age` ago to `<LIST>.archive.json`
+/// and returns how many were moved. The list itself isn't saved.
+pub fn archive_completed_tasks(
+    meta_path: &Path,
+    tasklist: &mut TaskList,
+    max_age: TimeDelta,
+    now: DateTime<Local>,
+) -> Result<usize, PlannerError> {
+    let mut archived: Vec<Task> = vec![];
+
+    let mut i = 0;
+    while i < tasklist.tasks.len() {
+        let task = &tasklist.tasks[i];
+
+        // Tasks waiting to be reopened aren't really done yet
+        if task.complete
+            && task.reopen_at.is_none()
+            && task.completed_at.is_some_and(|x| now - x > max_age)
+        {
+            archived.push(tasklist.tasks.remove(i));
+        } else {
+            i += 1;
+        }
+    }
+
+    if archived.is_empty() {
+        return Ok(0);
+    }
+
+    for task in archived.iter() {
+        remove_references_to(tasklist, task.id);
+    }
+
+    let archive_path = meta_path.with_extension("archive.json");
+    let mut archive = TaskList::default();
+
+    if archive_path.exists() {
+        archive = read_task_list(&archive_path)?;
+    }
+
+    let count = archived.len();
+    archive.tasks.append(&mut archived);
+
+    if let Err(e) = fs::write(&archive_path, serde_json::to_string(&archive).unwrap()) {
+        return Err(PlannerError::Generic(format!(
+            "Could not write to the archive: {e}"
+        )));
+    }
+
+    Ok(count)
+}
+
+/// Opens again every completed task whose `reopen_at` has passed, returning
+/// their names. A recurring task takes back the place of the next occurrence
+/// checking it off created, which is removed while still open.
+pub fn reopen_due_tasks(tasklist: &mut TaskList, now: DateTime<Local>) -> Vec<String> {
+    let mut reopened: Vec<String> = vec![];
+
+    let due: Vec<usize> = tasklist
+        .tasks
+        .iter()
+        .filter(|x| x.complete && x.reopen_at.is_some_and(|x| x <= now))
+        .map(|x| x.id)
+        .collect();
+
+    for id in due {
+        let idx = get_task_index(tasklist, id).unwrap();
+
+        tasklist.tasks[idx].reset_completion();
+        reopened.push(tasklist.tasks[idx].name.clone());
+
+        let next = tasklist.tasks[idx]
+            .recurrence
+            .as_ref()
+            .and_then(|x| x.next_id);
+
+        if let Some(next) = next
+            && let Some(next_idx) = get_task_index(tasklist, next)
+            && !tasklist.tasks[next_idx].complete
+        {
+            tasklist.tasks.remove(next_idx);
+            remove_references_to(tasklist, next);
+
+            let idx = get_task_index(tasklist, id).unwrap();
+            tasklist.tasks[idx].recurrence.as_mut().unwrap().next_id = None;
+        }
+    }
+
+    reopened
+}
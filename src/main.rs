@@ -1,13 +1,73 @@
-use std::{env::current_dir, fs, process::exit};
-
-use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
-use clap::{Args, Parser, Subcommand};
+use std::{
+    env::current_dir,
+    fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::{self, Command},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+    },
+    thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, Local, TimeDelta};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+#[cfg(feature = "chart")]
+use planner::chart::*;
+use planner::*;
+use uuid::Uuid;
+
+const EXIT_CODES_HELP: &str = "Environment:
+  PLANNER_LIST         Default for --list
+  PLANNER_FILE         Default for --file
+  PLANNER_DATE_FORMAT  Default for --date-format
+  PLANNER_NO_COLOR     Set to anything but '0' or 'false' for --no-color
+
+Flags take precedence over these, and these over the config file.
+
+Exit codes:
+  0  Success
+  1  Generic error
+  2  Planner is not initialized in this directory
+  3  Task not found
+  4  Invalid date, number or file contents
+
+With --json, errors are written to stderr as a JSON object like
+{\"error\": \"task_not_found\", \"message\": \"Task #9 not found\", \"id\": 9}";
 
 #[derive(Parser)]
+#[command(version, after_help = EXIT_CODES_HELP)]
 struct Cli {
     #[command(subcommand)]
     #[command(help = "Subcommand to execute")]
     command: Commands,
+
+    #[arg(help = "The name of the task list to use, stored in '<LIST>.json' [default: planner]")]
+    #[arg(long, global = true)]
+    list: Option<String>,
+
+    #[arg(help = "The path of the task list file, instead of deriving it from --list")]
+    #[arg(long, global = true)]
+    file: Option<String>,
+
+    #[arg(help = "Disable colors in the output")]
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    #[arg(help = "The strftime format used to show dates [default: '%Y-%m-%d at %H:%M:%S']")]
+    #[arg(long, global = true)]
+    date_format: Option<String>,
+
+    #[arg(help = "Fail when the list or config file contains unknown fields")]
+    #[arg(long, global = true)]
+    strict: bool,
+
+    #[arg(help = "How states are highlighted, overrides the config's color_scheme")]
+    #[arg(long, global = true, value_enum)]
+    color_scheme: Option<ColorScheme>,
 }
 
 #[derive(Subcommand)]
@@ -16,528 +76,3183 @@ enum Commands {
     Init(InitArgs),
 
     #[command(about = "List all current tasks")]
-    List,
+    List(ListArgs),
 
     #[command(about = "Adds a task")]
-    Add(AddArgs),
+    Add(Box<AddArgs>),
 
     #[command(about = "Removes a task")]
     Rm(RmArgs),
 
     #[command(about = "Marks a task as complete")]
     Check(CheckArgs),
+
+    #[command(about = "Marks a completed task as not complete")]
+    Uncheck(UncheckArgs),
+
+    #[command(about = "Moves a task to another list")]
+    Mv(MvArgs),
+
+    #[command(about = "Shows completion statistics")]
+    Stats(StatsArgs),
+
+    #[command(about = "Writes a Markdown summary of the current period")]
+    Report(ReportArgs),
+
+    #[command(about = "Exports the tasks to a file format for sharing")]
+    Export(ExportArgs),
+
+    #[command(about = "Prints a shell completion script")]
+    Completions(CompletionsArgs),
+
+    #[command(about = "Shows a saved list view, or saves and removes them")]
+    View(ViewArgs),
+
+    #[command(about = "Adds the tasks from an exported file to the list")]
+    Import(ImportArgs),
+
+    #[command(about = "Sets how much of a task is done, 100 checks it off")]
+    Progress(ProgressArgs),
+
+    #[command(about = "Sets or clears the due date of a task")]
+    Due(DueArgs),
+
+    #[command(about = "Moves the due date of tasks later by a duration")]
+    Snooze(SnoozeArgs),
+
+    #[command(about = "Shows the planner and list file versions")]
+    Version(VersionArgs),
+
+    #[command(about = "Adds to or subtracts from the points of a task")]
+    Bump(BumpArgs),
+
+    #[command(about = "Sets how points are written, as plain numbers, Fibonacci or T-shirt sizes")]
+    SetPointsScheme(SetPointsSchemeArgs),
+
+    #[command(about = "Changes the name, points or due date of a task")]
+    Edit(EditArgs),
+
+    #[command(about = "Rebuilds a task list from its audit log")]
+    Replay(ReplayArgs),
+
+    #[command(about = "Shows how the list changed compared to a snapshot")]
+    Diff(DiffArgs),
+
+    #[command(about = "Archives completed tasks older than a given age")]
+    Tidy(TidyArgs),
+
+    #[command(about = "Lists the next page of tasks after the one shown last")]
+    NextPage(PageArgs),
+
+    #[command(about = "Lists the page of tasks before the one shown last")]
+    PrevPage(PageArgs),
+
+    #[command(about = "Shows tasks whose reminder time has come")]
+    Remind,
+
+    #[command(about = "Keeps running and shows each task once its reminder time comes")]
+    WatchDue(WatchDueArgs),
+
+    #[command(about = "Moves a task one place up among its siblings in the list")]
+    Up(MoveArgs),
+
+    #[command(about = "Moves a task one place down among its siblings in the list")]
+    Down(MoveArgs),
+
+    #[command(about = "Renames or removes a tag on every task")]
+    Tag(TagArgs),
+
+    #[command(about = "Lists every tag with how many tasks have it")]
+    Tags,
+
+    #[command(about = "Works with the config file")]
+    Config(ConfigArgs),
+
+    #[command(about = "Lists the snapshots taken before each change, newest first")]
+    Snapshots,
+
+    #[command(about = "Rolls the list back to a snapshot")]
+    Restore(RestoreArgs),
+
+    #[command(about = "Closes the gaps in task ids left by removed tasks")]
+    Renumber(RenumberArgs),
+
+    #[command(about = "Shows the points earned this period against the config's points_goal")]
+    Goal(GoalArgs),
+}
+
+#[derive(Args)]
+struct GoalArgs {
+    #[arg(help = "The period to count completed tasks in, ending now")]
+    #[arg(long, value_enum, default_value_t = Period::Week)]
+    period: Period,
+
+    #[arg(help = "The points to aim for, overriding the config's points_goal")]
+    #[arg(long)]
+    target: Option<u32>,
+}
+
+#[derive(Args)]
+struct PageArgs {
+    #[arg(help = "How many tasks to show on each page from now on")]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    page_size: Option<u32>,
+}
+
+#[derive(Args)]
+struct WatchDueArgs {
+    #[arg(help = "How often to read the list again, like '1m' or '1h'")]
+    #[arg(long, value_name = "DURATION", default_value = "1m")]
+    interval: String,
+}
+
+#[derive(Args)]
+struct RenumberArgs {
+    #[arg(help = "Don't ask before renumbering")]
+    #[arg(long, short)]
+    yes: bool,
+}
+
+#[derive(Args)]
+struct RestoreArgs {
+    #[arg(help = "The number of the snapshot in 'snapshots', or its timestamp")]
+    snapshot: String,
+
+    #[arg(help = "Don't ask before restoring")]
+    #[arg(long, short)]
+    yes: bool,
+}
+
+#[derive(Args)]
+struct TagArgs {
+    #[command(subcommand)]
+    action: TagAction,
+}
+
+#[derive(Subcommand)]
+enum TagAction {
+    #[command(about = "Renames a tag, merging it into NEW on tasks that have both")]
+    Rename {
+        #[arg(help = "The tag to rename")]
+        old: String,
+
+        #[arg(help = "The new name of the tag")]
+        new: String,
+    },
+
+    #[command(about = "Removes a tag from every task")]
+    Rm {
+        #[arg(help = "The tag to remove")]
+        tag: String,
+    },
+}
+
+#[derive(Args)]
+struct ConfigArgs {
+    #[command(subcommand)]
+    action: ConfigAction,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    #[command(about = "Opens the config file in $EDITOR, creating it with the defaults if needed")]
+    Edit,
 }
 
 #[derive(Args)]
 struct InitArgs {
     #[arg(help = "The directory where planner should be initialized")]
     dir: Option<String>,
+
+    #[arg(help = "A JSON or Markdown checklist file of tasks to start the list with")]
+    #[arg(long)]
+    template: Option<String>,
+
+    #[arg(help = "Start the list with a few example tasks")]
+    #[arg(long, conflicts_with = "template")]
+    sample: bool,
+
+    #[arg(help = "Overwrite the list if it already exists")]
+    #[arg(long)]
+    force: bool,
+
+    #[arg(help = "Commit the list to git after every change")]
+    #[arg(long)]
+    git: bool,
+}
+
+#[derive(Args)]
+struct ListArgs {
+    #[arg(help = "Show tasks indented under the tasks they depend on")]
+    #[arg(long)]
+    tree: bool,
+
+    #[arg(help = "Show the urgency score of each task")]
+    #[arg(long)]
+    urgency: bool,
+
+    #[arg(help = "Show a flat list sorted by urgency, most urgent first")]
+    #[arg(long)]
+    sort_urgency: bool,
+
+    #[arg(help = "Show a flat list sorted by this key")]
+    #[arg(long, value_enum, conflicts_with_all = ["sort_urgency", "tree"])]
+    sort: Option<SortKey>,
+
+    #[arg(help = "Break ties in --sort by this key instead of the stored order")]
+    #[arg(long, value_enum, requires = "sort")]
+    sort_secondary: Option<SortKey>,
+
+    #[arg(help = "Show the tasks numbered 1, 2, 3... in the order they're shown")]
+    #[arg(long)]
+    flat_ids: bool,
+
+    #[arg(help = "Show only the tasks shown near this one, which is marked with '>'")]
+    #[arg(long, value_name = "ID", conflicts_with_all = ["tree", "group_complete"])]
+    around: Option<usize>,
+
+    #[arg(help = "How many tasks to show before and after the one given to --around")]
+    #[arg(long, value_name = "N", default_value_t = 3, requires = "around")]
+    context: usize,
+
+    #[arg(help = "Print only one uncolored line per task, without header or summary")]
+    #[arg(long)]
+    plain: bool,
+
+    #[arg(help = "Print one character per task and the completion percentage on one line")]
+    #[arg(long)]
+    compact: bool,
+
+    #[arg(help = "Show dates relative to now, like 'in 3 days'")]
+    #[arg(long, conflicts_with = "absolute_dates")]
+    relative_dates: bool,
+
+    #[arg(help = "Show dates as timestamps, overriding the config's date_display")]
+    #[arg(long)]
+    absolute_dates: bool,
+
+    #[arg(help = "Print the tasks as JSON on a single line, always sorted by id")]
+    #[arg(long)]
+    json: bool,
+
+    #[arg(help = "Print the tasks as a Markdown checklist, without colors")]
+    #[arg(long, conflicts_with = "json")]
+    as_markdown: bool,
+
+    #[arg(help = "Add the points and due date of each task to the Markdown checklist")]
+    #[arg(long, requires = "as_markdown")]
+    markdown_details: bool,
+
+    #[arg(help = "Indent the JSON output for reading")]
+    #[arg(long, requires = "json")]
+    pretty: bool,
+
+    #[arg(
+        help = "Print the JSON as an object with the list's name and version, or a bare array of tasks"
+    )]
+    #[arg(long, value_enum, default_value = "object", requires = "json")]
+    json_shape: JsonShape,
+
+    #[arg(help = "Print only the array of tasks, the same as --json-shape array")]
+    #[arg(long, requires = "json", conflicts_with = "json_shape")]
+    bare: bool,
+
+    #[arg(help = "Add when the JSON was generated, which makes it differ between runs")]
+    #[arg(long, requires = "json", conflicts_with = "bare")]
+    generated_at: bool,
+
+    #[arg(help = "Add each task's time left, overdue state and urgency to the JSON")]
+    #[arg(long, requires = "json")]
+    include_computed: bool,
+
+    #[arg(help = "Count the partial progress of open tasks in the points total")]
+    #[arg(long)]
+    weight_progress: bool,
+
+    #[arg(help = "Add the flags of a view saved with 'view save'")]
+    #[arg(long)]
+    view: Option<String>,
+
+    #[arg(help = "Show only completed tasks")]
+    #[arg(long)]
+    completed: bool,
+
+    #[arg(help = "Show only tasks with a due date")]
+    #[arg(long, conflicts_with = "only_no_due")]
+    only_with_due: bool,
+
+    #[arg(help = "Show only tasks without a due date")]
+    #[arg(long)]
+    only_no_due: bool,
+
+    #[arg(help = "Show only tasks with at least this priority, a number or low, medium or high")]
+    #[arg(long, value_name = "LEVEL")]
+    min_priority: Option<String>,
+
+    #[arg(help = "Show only tasks with this id or a higher one")]
+    #[arg(long, value_name = "ID")]
+    min_id: Option<usize>,
+
+    #[arg(help = "Show only tasks with this id or a lower one")]
+    #[arg(long, value_name = "ID")]
+    max_id: Option<usize>,
+
+    #[arg(help = "Show only this page of tasks, remembered for next-page and prev-page")]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    page: Option<u32>,
+
+    #[arg(help = "How many tasks each page has, remembered for later pages [default: 20]")]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    page_size: Option<u32>,
+
+    #[arg(help = "Show the page this many pages from the last one shown")]
+    #[arg(long, hide = true, allow_hyphen_values = true, conflicts_with = "page")]
+    page_delta: Option<isize>,
+
+    #[arg(
+        help = "Mark tasks completed on or after this date, or show only those with --completed"
+    )]
+    #[arg(long, value_name = "DATE")]
+    since: Option<String>,
+
+    #[arg(help = "Print only this field of each task, one per line")]
+    #[arg(long, value_enum)]
+    field: Option<TaskField>,
+
+    #[arg(help = "Print a table of these comma-separated fields, like 'id,name,due'")]
+    #[arg(long, value_enum, value_delimiter = ',', conflicts_with = "field")]
+    columns: Vec<TaskField>,
+
+    #[arg(help = "Print a histogram of how many tasks fall in each bucket instead")]
+    #[arg(long, value_enum)]
+    count_by: Option<CountBy>,
+
+    #[arg(help = "Leave out the points total and budget warning below the tasks")]
+    #[arg(long)]
+    no_summary: bool,
+
+    #[arg(help = "Show the points left in open tasks instead of the completed share")]
+    #[arg(long, conflicts_with = "weight_progress")]
+    remaining_only: bool,
+
+    #[arg(help = "Add how many tasks are overdue to the summary")]
+    #[arg(long)]
+    highlight_overdue_count: bool,
+
+    #[arg(help = "Count tasks as overdue only this long after their due date, like '2h'")]
+    #[arg(long, value_name = "DURATION")]
+    overdue_threshold: Option<String>,
+
+    #[arg(help = "Print the JSON Schema of the list file instead")]
+    #[arg(long, hide = true)]
+    json_schema: bool,
+
+    #[arg(help = "Show open tasks first, then completed ones, each with their points")]
+    #[arg(long)]
+    group_complete: bool,
+
+    #[arg(help = "Print everything at once instead of through $PAGER when it doesn't fit")]
+    #[arg(long)]
+    no_pager: bool,
+
+    #[arg(help = "Render for this many columns instead of the terminal width")]
+    #[arg(long, value_name = "COLS")]
+    width_for: Option<usize>,
+
+    #[arg(help = "Shorten task names longer than this many columns, ending them with '…'")]
+    #[arg(long, value_name = "N")]
+    truncate_names: Option<usize>,
+
+    #[arg(help = "Print only the number of open tasks due before the end of today")]
+    #[arg(long)]
+    due_today_count: bool,
+}
+
+#[derive(Args)]
+struct AddArgs {
+    #[arg(help = "The name of the task")]
+    #[arg(required_unless_present_any = ["duplicate", "interactive"])]
+    taskname: Option<String>,
+
+    #[arg(
+        help = "How many points the task should reward, or a size like 'M' with the T-shirt scheme"
+    )]
+    #[arg(long, short, required_unless_present_any = ["duplicate", "interactive"])]
+    points: Option<String>,
+
+    #[arg(help = "Ask for the name, points, due date, tags and priority one at a time")]
+    #[arg(long, short, conflicts_with = "duplicate")]
+    interactive: bool,
+
+    #[arg(help = "The id to give the task instead of the first free one")]
+    #[arg(long)]
+    id: Option<usize>,
+
+    #[arg(help = "Copy this task into a new open one, optionally renamed by TASKNAME")]
+    #[arg(long, value_name = "ID")]
+    #[arg(conflicts_with_all = [
+        "due", "start", "parent_id", "resources", "tags", "depends_on", "every",
+        "remind_before",
+    ])]
+    duplicate: Option<usize>,
+
+    #[arg(help = "Due date of the task, given as 'yyyy-mm-dd HH:MM:SS' or RFC3339")]
+    #[arg(long, group = "due")]
+    due_date: Option<String>,
+
+    #[arg(help = "Make the task due this long from now, like '2h', '3d' or '1w'")]
+    #[arg(long, value_name = "DURATION", group = "due")]
+    due_from_now: Option<String>,
+
+    #[arg(help = "Start time of the task, given as 'yyyy-mm-dd HH:MM:SS' or RFC3339")]
+    #[arg(long, group = "start")]
+    start_time: Option<String>,
+
+    #[arg(help = "Start work on the task this long from now, like '2h', '3d' or '1w'")]
+    #[arg(long, value_name = "DURATION", group = "start")]
+    start_from_now: Option<String>,
+
+    #[arg(help = "A number representing how important the task is to complete")]
+    #[arg(long, default_value_t = 0)]
+    priority: u8,
+
+    #[arg(help = "The id of the parent of this task")]
+    #[arg(long)]
+    parent_id: Option<usize>,
+
+    #[arg(help = "A comma-separated list of resources to allocate to this task")]
+    #[arg(long, short)]
+    resources: Option<String>,
+
+    #[arg(help = "A comma-separated list of tags for this task")]
+    #[arg(long, short)]
+    tags: Option<String>,
+
+    #[arg(help = "A comma-separated list of ids of tasks that must be completed first")]
+    #[arg(long)]
+    depends_on: Option<String>,
+
+    #[arg(help = "Allow a due date that is already in the past")]
+    #[arg(long)]
+    allow_past: bool,
+
+    #[arg(help = "Allow a name that an incomplete task already has")]
+    #[arg(long)]
+    allow_duplicate: bool,
+
+    #[arg(help = "Repeat the task at this interval once checked off, e.g. '1d' or '2w'")]
+    #[arg(long)]
+    every: Option<String>,
+
+    #[arg(help = "Don't repeat the task past this date, same formats as --due-date")]
+    #[arg(long, requires = "every")]
+    until: Option<String>,
+
+    #[arg(help = "Create at most this many occurrences of the task, including this one")]
+    #[arg(long, requires = "every")]
+    count: Option<u32>,
+
+    #[arg(help = "Remind this long before the due date, e.g. '1d', can be repeated")]
+    #[arg(long, requires = "due")]
+    remind_before: Vec<String>,
+
+    #[arg(help = "Print the id of the new task on its own line")]
+    #[arg(long)]
+    print_id: bool,
+
+    #[arg(help = "Don't print messages or warnings, only the id with --print-id")]
+    #[arg(long, short)]
+    quiet: bool,
+}
+
+#[derive(Args)]
+struct RmArgs {
+    #[arg(help = "The id of the task")]
+    #[arg(required_unless_present_any = ["completed", "overdue", "tag"])]
+    #[arg(conflicts_with_all = ["completed", "overdue", "tag"])]
+    task_id: Option<usize>,
+
+    #[arg(help = "Remove every completed task")]
+    #[arg(long)]
+    completed: bool,
+
+    #[arg(help = "Remove every overdue task")]
+    #[arg(long)]
+    overdue: bool,
+
+    #[arg(help = "Remove every task with this tag")]
+    #[arg(long)]
+    tag: Option<String>,
+
+    #[arg(help = "Don't ask before removing several tasks")]
+    #[arg(long, short)]
+    yes: bool,
+
+    #[arg(help = "Show what would be removed and which dependencies would be dropped")]
+    #[arg(long)]
+    dry_run: bool,
+
+    #[arg(help = "Remove tasks other tasks depend on, dropping those dependencies")]
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Args)]
+struct CheckArgs {
+    #[arg(help = "The id of the task")]
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    task_id: Option<usize>,
+
+    #[arg(help = "Check off every open task, or only those matching --tag and --overdue")]
+    #[arg(long)]
+    all: bool,
+
+    #[arg(help = "With --all, only check off tasks with this tag")]
+    #[arg(long, requires = "all")]
+    tag: Option<String>,
+
+    #[arg(help = "With --all, only check off overdue tasks")]
+    #[arg(long, requires = "all")]
+    overdue: bool,
+
+    #[arg(help = "A note describing how the task was completed")]
+    #[arg(long, short)]
+    note: Option<String>,
+
+    #[arg(help = "When the task was completed if not now, same formats as --due-date")]
+    #[arg(long)]
+    at: Option<String>,
+
+    #[arg(help = "Reopen the task after this long, like '2d', unless it's unchecked before")]
+    #[arg(long, value_name = "DURATION")]
+    undo_after: Option<String>,
+
+    #[arg(help = "Also check off the open tasks this one depends on, and theirs")]
+    #[arg(long, conflicts_with = "all")]
+    close_deps: bool,
+}
+
+#[derive(Args)]
+struct UncheckArgs {
+    #[arg(help = "The id of the task")]
+    task_id: usize,
+}
+
+#[derive(Args)]
+struct StatsArgs {
+    #[arg(help = "Break the statistics down per tag")]
+    #[arg(long)]
+    by_tag: bool,
+
+    #[arg(help = "Print the statistics as JSON")]
+    #[arg(long)]
+    json: bool,
+
+    #[arg(help = "Show the points left in open tasks instead of the completed share")]
+    #[arg(long)]
+    remaining_only: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// A self-contained page with a table of the tasks
+    Html,
+    /// One JSON task object per line
+    Jsonl,
+    /// A bar chart of the points as a PNG image, needs --output
+    #[cfg(feature = "chart")]
+    Png,
+    /// A bar chart of the points as an SVG image
+    #[cfg(feature = "chart")]
+    Svg,
+}
+
+#[derive(Args)]
+struct CompletionsArgs {
+    #[arg(help = "The shell to print completions for")]
+    #[arg(value_enum, required_unless_present = "check")]
+    shell: Option<Shell>,
+
+    #[arg(help = "Check that every command and flag has help text and a unique name")]
+    #[arg(long, hide = true)]
+    check: bool,
+}
+
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true)]
+struct ViewArgs {
+    #[command(subcommand)]
+    action: Option<ViewAction>,
+
+    #[arg(help = "The saved view to show")]
+    name: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum ViewAction {
+    #[command(
+        about = "Saves flags of 'list' under a name, e.g. 'view save due -- --only-with-due'"
+    )]
+    Save {
+        #[arg(help = "The name of the view")]
+        name: String,
+
+        #[arg(help = "The flags to pass to 'list'")]
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+
+    #[command(about = "Removes a saved view")]
+    Rm {
+        #[arg(help = "The name of the view")]
+        name: String,
+    },
+
+    #[command(about = "Lists the saved views")]
+    Ls,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum JsonShape {
+    /// `{"list_name": ..., "version": ..., "tasks": [...]}`
+    Object,
+    /// `[...]`, only the tasks
+    Array,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ImportFormat {
+    /// One JSON task object per line, as written by `export --format jsonl`
+    Jsonl,
 }
 
-#[derive(Args)]
-struct AddArgs {
-    #[arg(help = "The name of the task")]
-    taskname: String,
+#[derive(Args)]
+struct ImportArgs {
+    #[arg(help = "The file to read the tasks from")]
+    #[arg(required_unless_present = "from", requires = "format")]
+    input: Option<String>,
+
+    #[arg(help = "The format of the file")]
+    #[arg(long, value_enum)]
+    format: Option<ImportFormat>,
+
+    #[arg(help = "What to do with tasks named like one already in the list")]
+    #[arg(long, value_enum, default_value_t = MergePolicy::Append)]
+    merge: MergePolicy,
+
+    #[arg(help = "Merge in the tasks of another planner file, skipping those already in the list")]
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["input", "format", "merge"])]
+    from: Option<String>,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    #[arg(help = "The format to export the tasks in")]
+    #[arg(long, value_enum)]
+    format: ExportFormat,
+
+    #[arg(help = "The file to write the export to, prints to stdout if omitted")]
+    #[arg(long, short)]
+    output: Option<String>,
+
+    #[cfg(feature = "chart")]
+    #[arg(help = "What the png and svg charts show")]
+    #[arg(long, value_enum, default_value = "points")]
+    chart: ChartKind,
+}
+
+#[derive(Args)]
+struct ReportArgs {
+    #[arg(help = "The period to report on, ending now")]
+    #[arg(long, value_enum, default_value_t = Period::Week)]
+    period: Period,
+
+    #[arg(help = "The file to write the report to, prints to stdout if omitted")]
+    #[arg(long, short)]
+    output: Option<String>,
+}
+
+#[derive(Args)]
+struct ProgressArgs {
+    #[arg(help = "The id of the task")]
+    task_id: usize,
+
+    #[arg(help = "How much of the task is done, from 0 to 100")]
+    #[arg(value_parser = clap::value_parser!(u8).range(..=100))]
+    percent: u8,
+}
+
+#[derive(Args)]
+struct DueArgs {
+    #[arg(help = "The id of the task")]
+    task_id: usize,
+
+    #[arg(help = "The new due date, given as 'yyyy-mm-dd HH:MM:SS' or RFC3339")]
+    #[arg(required_unless_present = "clear", conflicts_with = "clear")]
+    date: Option<String>,
+
+    #[arg(help = "Remove the due date instead")]
+    #[arg(long)]
+    clear: bool,
+}
+
+#[derive(Args)]
+struct VersionArgs {
+    #[arg(help = "Also show the schema version the task list was written with")]
+    #[arg(long)]
+    data: bool,
+
+    #[arg(help = "Print the versions as JSON")]
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args)]
+struct SnoozeArgs {
+    #[arg(help = "The id of the task, leave out with --all")]
+    #[arg(required_unless_present = "all")]
+    task_id: Option<usize>,
+
+    #[arg(help = "How long to push the due date back, e.g. '1d' or '3h'")]
+    #[arg(long, value_name = "DURATION")]
+    by: String,
+
+    #[arg(help = "Snooze every open task with a due date, or only those matching the filters")]
+    #[arg(long, conflicts_with = "task_id")]
+    all: bool,
+
+    #[arg(help = "With --all, only snooze overdue tasks")]
+    #[arg(long, requires = "all")]
+    overdue: bool,
+
+    #[arg(help = "With --all, only snooze tasks with this tag")]
+    #[arg(long, requires = "all")]
+    tag: Option<String>,
+}
+
+#[derive(Args)]
+struct BumpArgs {
+    #[arg(help = "The id of the task")]
+    task_id: usize,
+
+    #[arg(help = "How many points to add, negative to subtract, e.g. '+2' or '-1'")]
+    #[arg(allow_negative_numbers = true)]
+    delta: i64,
+}
+
+#[derive(Args)]
+struct SetPointsSchemeArgs {
+    #[arg(help = "The scheme to use from now on")]
+    #[arg(value_enum)]
+    scheme: PointsScheme,
+}
+
+#[derive(Args)]
+struct EditArgs {
+    #[arg(help = "The id of the task")]
+    task_id: usize,
+
+    #[arg(help = "The new name of the task")]
+    #[arg(long)]
+    name: Option<String>,
+
+    #[arg(
+        help = "How many points the task should reward, or a size like 'M' with the T-shirt scheme"
+    )]
+    #[arg(long, short)]
+    points: Option<String>,
+
+    #[arg(help = "The new due date, given as 'yyyy-mm-dd HH:MM:SS' or RFC3339")]
+    #[arg(long)]
+    due_date: Option<String>,
+
+    #[arg(help = "Open the task as JSON in $EDITOR instead")]
+    #[arg(long, short = 'e', conflicts_with_all = ["name", "points", "due_date"])]
+    interactive: bool,
+}
+
+#[derive(Args)]
+struct ReplayArgs {
+    #[arg(help = "The audit log to replay, defaults to '<LIST>.log'")]
+    #[arg(long)]
+    log: Option<String>,
+
+    #[arg(help = "The file to write the rebuilt task list to")]
+    #[arg(long, short)]
+    output: String,
+}
+
+#[derive(Args)]
+struct MoveArgs {
+    #[arg(help = "The id of the task")]
+    task_id: usize,
+}
+
+#[derive(Args)]
+struct DiffArgs {
+    #[arg(help = "The snapshot to compare against, defaults to the backup '<LIST>.json.bak'")]
+    snapshot: Option<String>,
+}
+
+#[derive(Args)]
+struct TidyArgs {
+    #[arg(
+        help = "Archive tasks completed longer ago than this, defaults to the config's auto_archive_after"
+    )]
+    #[arg(long)]
+    older_than: Option<String>,
+}
+
+#[derive(Args)]
+struct MvArgs {
+    #[arg(help = "The id of the task")]
+    task_id: usize,
+
+    #[arg(help = "The name of the list to move the task to")]
+    to: String,
+
+    #[arg(help = "The name of the list to move the task from, defaults to --list")]
+    #[arg(long)]
+    from: Option<String>,
+}
+
+/// Whether `Exit::exit` writes errors as JSON, set once the command is known
+/// to be printing JSON.
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+
+fn set_json_errors(on: bool) {
+    JSON_ERRORS.store(on, AtomicOrdering::Relaxed);
+}
+
+/// Ends the program on an error with its exit code, printing it as text or
+/// as JSON to stderr while `JSON_ERRORS` is set.
+trait Exit {
+    fn exit(&self) -> !;
+}
+
+impl Exit for PlannerError {
+    fn exit(&self) -> ! {
+        if JSON_ERRORS.load(AtomicOrdering::Relaxed) {
+            eprintln!("{}", self.to_json());
+        } else {
+            println!("{self}");
+        }
+
+        process::exit(self.exit_code());
+    }
+}
+
+/// Like `read_config`, but exits on failure.
+fn get_config() -> Config {
+    match read_config() {
+        Ok(x) => x,
+        Err(e) => e.exit(),
+    }
+}
+
+/// Like `read_task_list`, but exits on failure.
+fn get_task_list(meta_path: &Path) -> TaskList {
+    match read_task_list(meta_path) {
+        Ok(x) => x,
+        Err(e) => e.exit(),
+    }
+}
+
+/// Like `write_task_list`, but exits on failure and prints its warnings.
+fn save_task_list(meta_path: &Path, tasklist: &TaskList, config: &Config) {
+    match write_task_list(meta_path, tasklist, config) {
+        Ok(warnings) => {
+            for x in warnings {
+                println!("Warning: {x}");
+            }
+        }
+        Err(e) => PlannerError::Generic(format!("Could not save the list: {e}")).exit(),
+    }
+}
+
+/// Like `set_config_value`, but exits on failure.
+fn save_config_value(key: &str, value: serde_json::Value) {
+    if let Err(e) = set_config_value(key, value) {
+        PlannerError::Generic(format!("Could not write to the config file: {e}")).exit();
+    }
+}
+
+/// Writes `contents` to the file given with `--output`, exiting on failure.
+fn write_output(path: &str, contents: String) {
+    if let Err(e) = fs::write(path, contents) {
+        PlannerError::Generic(format!("Could not write to '{path}': {e}")).exit();
+    }
+}
+
+/// Like `parse_time`, but exits on failure.
+fn get_time_from_string(date: String) -> DateTime<Local> {
+    match parse_time(&date) {
+        Ok(x) => x,
+        Err(e) => e.exit(),
+    }
+}
+
+/// Asks a yes/no question on stdin. Returns false when stdin isn't a
+/// terminal, so scripts never block waiting for an answer.
+fn confirm(question: &str) -> bool {
+    if !io::stdin().is_terminal() {
+        return false;
+    }
+
+    print!("{question} [y/N] ");
+    io::stdout().flush().unwrap();
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+
+    let answer = answer.trim().to_lowercase();
+
+    answer == "y" || answer == "yes"
+}
+
+/// Stops adding a task named like an open one unless `allow` is given or
+/// the user confirms, warning about it otherwise.
+fn check_new_name(tasklist: &TaskList, name: &str, allow: bool, quiet: bool) {
+    let wanted_name = name.trim().to_lowercase();
+
+    let Some(task) = tasklist
+        .tasks
+        .iter()
+        .find(|x| !x.complete && x.name.trim().to_lowercase() == wanted_name)
+    else {
+        return;
+    };
+
+    let msg = format!("Task #{} '{}' already exists", task.id, task.name);
+
+    if allow {
+        if !quiet {
+            println!("Warning: {msg}");
+        }
+    } else if !confirm(format!("{msg}, add another one?").as_str()) {
+        PlannerError::Generic(format!("{msg}, use --allow-duplicate to add it anyway")).exit();
+    }
+}
+
+/// Stops adding a task due in the past unless `allow` is given or the user
+/// confirms, warning about it otherwise.
+fn check_new_due_date(due: Option<DateTime<Local>>, allow: bool, quiet: bool) {
+    if due.is_none_or(|x| x >= Local::now()) {
+        return;
+    }
+
+    if allow {
+        if !quiet {
+            println!("Warning: due date is in the past");
+        }
+    } else if !confirm("Due date is in the past, add the task anyway?") {
+        PlannerError::Generic(
+            "Due date is in the past, use --allow-past to add it anyway".to_string(),
+        )
+        .exit();
+    }
+}
+
+/// Asks for a value on stdin, showing `default` in brackets and using it
+/// for an empty answer. Asks again, after printing why, until `check`
+/// accepts the answer. Exits if stdin ends first.
+fn prompt(
+    question: &str,
+    default: &str,
+    check: impl Fn(&str) -> Result<(), PlannerError>,
+) -> String {
+    loop {
+        print!("{question} [{default}]: ");
+        io::stdout().flush().unwrap();
+
+        let mut answer = String::new();
+
+        match io::stdin().read_line(&mut answer) {
+            Ok(0) | Err(_) => {
+                println!();
+                PlannerError::Generic("No answer given".to_string()).exit();
+            }
+            Ok(_) => {}
+        }
+
+        let mut answer = answer.trim().to_string();
+
+        if answer.is_empty() {
+            answer = default.to_string();
+        }
+
+        match check(&answer) {
+            Ok(()) => return answer,
+            Err(e) => println!("{e}"),
+        }
+    }
+}
+
+/// The width of the terminal in columns, or 80 when it is unknown, e.g.
+/// because the output is piped.
+fn terminal_width() -> usize {
+    match terminal_size::terminal_size() {
+        Some((terminal_size::Width(w), _)) if w > 0 => w as usize,
+        _ => 80,
+    }
+}
+
+/// Prints `text`, going through `$PAGER` (or `less -R`) when stdout is a
+/// terminal too short to show it all. Falls back to printing directly when
+/// the pager can't be started.
+fn page_output(text: &str, no_pager: bool) {
+    let height = match terminal_size::terminal_size() {
+        Some((_, terminal_size::Height(h))) if h > 0 => h as usize,
+        _ => usize::MAX,
+    };
+
+    if no_pager || !io::stdout().is_terminal() || text.lines().count() < height {
+        print!("{text}");
+        return;
+    }
+
+    let pager = std::env::var("PAGER").unwrap_or("less -R".to_string());
+    let mut words = pager.split_whitespace();
+
+    let Some(program) = words.next() else {
+        print!("{text}");
+        return;
+    };
+
+    let child = Command::new(program)
+        .args(words)
+        .stdin(std::process::Stdio::piped())
+        .spawn();
+
+    let Ok(mut child) = child else {
+        print!("{text}");
+        return;
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager closing early, e.g. quitting less, isn't an error
+        let _ = stdin.write_all(text.as_bytes());
+    }
+
+    let _ = child.wait();
+}
+
+/// Opens `path` in `editor` and reads it back once the editor exits,
+/// removing the file either way. `what` names what is edited in errors.
+fn edit_file(editor: &str, path: &Path, what: &str) -> Result<String, PlannerError> {
+    let mut words = editor.split_whitespace();
+
+    let Some(program) = words.next() else {
+        let _ = fs::remove_file(path);
+        return Err(PlannerError::Generic("$EDITOR is empty".to_string()));
+    };
+
+    let status = Command::new(program).args(words).arg(path).status();
+
+    let raw = fs::read_to_string(path);
+    let _ = fs::remove_file(path);
+
+    match status {
+        Ok(x) if x.success() => {}
+        _ => {
+            return Err(PlannerError::Generic(format!(
+                "Editor '{editor}' failed, {what} left unchanged"
+            )));
+        }
+    }
+
+    match raw {
+        Ok(x) => Ok(x),
+        Err(_) => Err(PlannerError::Generic(format!(
+            "Could not read the edited {what}"
+        ))),
+    }
+}
+
+/// Writes `contents` to a new file in the temporary directory. The name is
+/// random and the file must not exist yet, so nothing already there, like a
+/// symlink planted by another user, gets written through.
+fn write_temp_file(prefix: &str, contents: &str) -> Result<PathBuf, PlannerError> {
+    let path = std::env::temp_dir().join(format!("{prefix}-{}.json", Uuid::new_v4()));
+
+    let error = || PlannerError::Generic(format!("Could not write '{}'", path.display()));
+
+    let Ok(mut file) = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+    else {
+        return Err(error());
+    };
+
+    if file.write_all(contents.as_bytes()).is_err() {
+        let _ = fs::remove_file(&path);
+        return Err(error());
+    }
+
+    Ok(path)
+}
+
+/// Writes `task` to a temporary file, opens it in `$EDITOR` (or `vi`) and
+/// reads it back once the editor exits. The id can't be changed.
+fn edit_task_in_editor(task: &Task) -> Result<Task, PlannerError> {
+    let path = write_temp_file("planner-task", &serde_json::to_string_pretty(task).unwrap())?;
+
+    let editor = std::env::var("EDITOR").unwrap_or("vi".to_string());
+    let raw = edit_file(&editor, &path, "task")?;
+
+    parse_edited_task(&raw, task)
+}
+
+/// Opens the config file in `editor`, creating it with every default first
+/// if it doesn't exist. The edits are made on a copy, which only replaces
+/// the config once `parse_edited_config` accepts it.
+fn edit_config_in_editor(editor: &str) -> Result<(), PlannerError> {
+    let config_path = get_config_path();
+
+    if !config_path.exists() {
+        let defaults = serde_json::to_string_pretty(&Config::default()).unwrap();
+
+        if fs::write(&config_path, defaults).is_err() {
+            return Err(PlannerError::Generic(format!(
+                "Could not write '{}'",
+                config_path.display()
+            )));
+        }
+    }
+
+    let Ok(current) = fs::read_to_string(&config_path) else {
+        return Err(PlannerError::Generic(format!(
+            "Could not read '{}'",
+            config_path.display()
+        )));
+    };
+
+    let path = write_temp_file("planner-config", &current)?;
+    let raw = edit_file(editor, &path, "config")?;
+
+    parse_edited_config(&raw)?;
+
+    if fs::write(&config_path, raw).is_err() {
+        return Err(PlannerError::Generic(format!(
+            "Could not write '{}'",
+            config_path.display()
+        )));
+    }
+
+    Ok(())
+}
+
+/// How long past due a task has to be to count as overdue, from the flag
+/// if given or else the config.
+fn overdue_threshold(flag: Option<&String>, config: &Config) -> TimeDelta {
+    let Some(raw) = flag.or(config.overdue_threshold.as_ref()) else {
+        return TimeDelta::zero();
+    };
+
+    match parse_duration(raw) {
+        Ok(x) => x,
+        Err(e) => e.exit(),
+    }
+}
+
+/// Reads the list every `interval` and shows the tasks whose reminder time
+/// has come, each once per due date, until interrupted with Ctrl+C.
+fn watch_due(meta_path: &Path, interval: TimeDelta) {
+    let stop = Arc::new(AtomicBool::new(false));
+    let handler_stop = stop.clone();
+
+    ctrlc::set_handler(move || handler_stop.store(true, AtomicOrdering::Relaxed))
+        .expect("Could not listen for Ctrl+C");
+
+    let mut notified: Vec<(usize, DateTime<Local>)> = vec![];
+
+    println!("Watching for reminders, press Ctrl+C to stop");
+
+    while !stop.load(AtomicOrdering::Relaxed) {
+        let now = Local::now();
+
+        // Read again every time to see tasks added or changed meanwhile
+        match read_task_list(meta_path) {
+            Ok(task_list) => {
+                for task in task_list.tasks.iter() {
+                    if active_reminder(task, now).is_none() {
+                        continue;
+                    }
+
+                    let key = (task.id, task.due_date.unwrap());
+
+                    if notified.contains(&key) {
+                        continue;
+                    }
+
+                    println!(
+                        "[{}] Task #{} '{}' is due in {}",
+                        now.format("%H:%M"),
+                        task.id,
+                        task.name,
+                        humanize_duration(key.1 - now)
+                    );
+
+                    notified.push(key);
+                }
+            }
+            Err(e) => println!("Warning: {e}"),
+        }
+
+        let wake = Local::now() + interval;
+
+        while Local::now() < wake && !stop.load(AtomicOrdering::Relaxed) {
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    println!("Stopped watching");
+}
+
+/// Asks for the fields of `add --interactive` that matter most, offering
+/// the values given on the command line as defaults.
+fn prompt_add_args(args: &mut AddArgs, config: &Config) {
+    let name = prompt("Name", args.taskname.as_deref().unwrap_or(""), |x| {
+        if x.trim() == "" {
+            return Err(PlannerError::Generic(
+                "A task name can't be empty".to_string(),
+            ));
+        }
+        Ok(())
+    });
+
+    let points = prompt("Points", args.points.as_deref().unwrap_or("1"), |x| {
+        parse_points(x, config.points_scheme).map(|_| ())
+    });
+
+    let due_default = match (&args.due_date, &args.due_from_now) {
+        (Some(x), _) | (_, Some(x)) => x.clone(),
+        _ => "none".to_string(),
+    };
+
+    let example = (Local::now() + TimeDelta::days(1)).format("%Y-%m-%d 18:00:00");
+
+    let due = prompt(
+        &format!("Due date, like '{example}' or '3d' from now"),
+        &due_default,
+        |x| {
+            if x == "none" || parse_duration(x).is_ok() {
+                return Ok(());
+            }
+            parse_time(x).map(|_| ())
+        },
+    );
+
+    let tags = prompt(
+        "Tags, separated by commas",
+        args.tags.as_deref().unwrap_or("none"),
+        |_| Ok(()),
+    );
+
+    let priority = prompt(
+        "Priority, a number or low, medium or high",
+        &args.priority.to_string(),
+        |x| parse_priority(x).map(|_| ()),
+    );
+
+    args.taskname = Some(name);
+    args.points = Some(points);
+    args.due_date = None;
+    args.due_from_now = None;
+
+    if parse_duration(&due).is_ok() {
+        args.due_from_now = Some(due);
+    } else if due != "none" {
+        args.due_date = Some(due);
+    }
+
+    args.tags = None;
+
+    if tags != "none" {
+        args.tags = Some(tags);
+    }
+
+    args.priority = parse_priority(&priority).unwrap();
+}
+
+/// Prints a line for each pair from `broken_dependencies`, like "Task #3
+/// 'Deploy' lost its dependency on #1 'Build'".
+fn print_broken_dependencies(task_list: &TaskList, broken: &[(usize, usize)], verb: &str) {
+    for (dependent, removed) in broken.iter() {
+        let a = get_task_index(task_list, *dependent).unwrap();
+        let b = get_task_index(task_list, *removed).unwrap();
+
+        println!(
+            "Task #{dependent} '{}' {verb} its dependency on #{removed} '{}'",
+            task_list.tasks[a].name, task_list.tasks[b].name
+        );
+    }
+}
+
+fn move_task_command(meta_path: &Path, task_id: usize, up: bool, config: &Config) {
+    let mut task_list = get_task_list(meta_path);
+
+    let Some(idx) = get_task_index(&task_list, task_id) else {
+        PlannerError::TaskNotFound(task_id).exit();
+    };
+
+    let name = task_list.tasks[idx].name.clone();
+
+    let Some(neighbor) = move_task(&mut task_list, task_id, up) else {
+        let edge = if up { "first" } else { "last" };
+        println!("Task '{name}' is already the {edge} of its siblings");
+        return;
+    };
+
+    save_task_list(meta_path, &task_list, config);
+
+    let neighbor_name = &task_list.tasks[get_task_index(&task_list, neighbor).unwrap()].name;
+    let direction = if up { "above" } else { "below" };
+
+    println!("Moved task '{name}' {direction} #{neighbor} '{neighbor_name}'");
+}
+
+/// Walks `cmd` and its subcommands and describes every command or flag
+/// without help text and every flag name used twice in one command.
+fn check_command_tree(cmd: &clap::Command, path: &str, problems: &mut Vec<String>) {
+    let mut longs: Vec<String> = vec![];
+    let mut shorts: Vec<char> = vec![];
+
+    for arg in cmd.get_arguments() {
+        let id = arg.get_id().as_str();
+
+        if id == "help" || id == "version" {
+            continue;
+        }
+
+        if arg.get_help().is_none() {
+            problems.push(format!("{path}: '{id}' has no help text"));
+        }
+
+        if let Some(x) = arg.get_long() {
+            if longs.contains(&x.to_string()) {
+                problems.push(format!("{path}: --{x} is used more than once"));
+            }
+            longs.push(x.to_string());
+        }
+
+        if let Some(x) = arg.get_short() {
+            if shorts.contains(&x) {
+                problems.push(format!("{path}: -{x} is used more than once"));
+            }
+            shorts.push(x);
+        }
+    }
+
+    let mut names: Vec<&str> = vec![];
+
+    for sub in cmd.get_subcommands() {
+        let name = sub.get_name();
+
+        if name == "help" {
+            continue;
+        }
+
+        if names.contains(&name) {
+            problems.push(format!("{path}: '{name}' is used more than once"));
+        }
+        names.push(name);
+
+        if sub.get_about().is_none() {
+            problems.push(format!("{path} {name}: has no help text"));
+        }
+
+        check_command_tree(sub, format!("{path} {name}").as_str(), problems);
+    }
+}
+
+/// The global flags of `cli` as they would be written on the command line.
+fn global_args(cli: &Cli) -> Vec<String> {
+    let mut args: Vec<String> = vec![];
+
+    if let Some(x) = &cli.list {
+        args.extend(["--list".to_string(), x.clone()]);
+    }
+
+    if let Some(x) = &cli.file {
+        args.extend(["--file".to_string(), x.clone()]);
+    }
+
+    if cli.no_color {
+        args.push("--no-color".to_string());
+    }
+
+    if let Some(x) = &cli.date_format {
+        args.extend(["--date-format".to_string(), x.clone()]);
+    }
+
+    if cli.strict {
+        args.push("--strict".to_string());
+    }
+
+    if let Some(x) = cli.color_scheme {
+        let name = x.to_possible_value().unwrap().get_name().to_string();
+        args.extend(["--color-scheme".to_string(), name]);
+    }
+
+    args
+}
+
+/// The command line to run instead when `cli` shows a saved view: `list`
+/// with the view's flags added to the ones given. `None` when no view is
+/// involved.
+fn expand_view(cli: &Cli, config: &Config) -> Option<Vec<String>> {
+    let program = std::env::args().next().unwrap_or("planner".to_string());
+
+    let (name, mut argv) = match &cli.command {
+        Commands::View(ViewArgs {
+            action: None,
+            name: Some(name),
+        }) => {
+            let mut argv = vec![program];
+            argv.extend(global_args(cli));
+            argv.push("list".to_string());
+
+            (name.clone(), argv)
+        }
+        Commands::List(ListArgs {
+            view: Some(name), ..
+        }) => {
+            let mut argv: Vec<String> = vec![];
+            let mut skip = false;
+
+            for arg in std::env::args() {
+                if skip {
+                    skip = false;
+                } else if arg == "--view" {
+                    skip = true;
+                } else if !arg.starts_with("--view=") {
+                    argv.push(arg);
+                }
+            }
+
+            (name.clone(), argv)
+        }
+        _ => return None,
+    };
+
+    let Some(flags) = config.views.get(&name) else {
+        PlannerError::Generic(format!("No view named '{name}'")).exit();
+    };
+
+    argv.extend(flags.iter().cloned());
+
+    Some(argv)
+}
+
+/// The command line to run instead for `next-page` and `prev-page`: `list`
+/// moving the remembered page by one. `None` for other commands.
+fn expand_page_command(cli: &Cli) -> Option<Vec<String>> {
+    let (delta, args) = match &cli.command {
+        Commands::NextPage(x) => (1, x),
+        Commands::PrevPage(x) => (-1, x),
+        _ => return None,
+    };
+
+    let mut argv = vec![std::env::args().next().unwrap_or("planner".to_string())];
+    argv.extend(global_args(cli));
+    argv.extend(["list".to_string(), format!("--page-delta={delta}")]);
+
+    if let Some(x) = args.page_size {
+        argv.extend(["--page-size".to_string(), x.to_string()]);
+    }
+
+    Some(argv)
+}
+
+/// Whether the command prints JSON, in which case errors are written as
+/// JSON too.
+fn prints_json(cli: &Cli) -> bool {
+    match &cli.command {
+        Commands::List(x) => x.json,
+        Commands::Stats(x) => x.json,
+        Commands::Version(x) => x.json,
+        _ => false,
+    }
+}
+
+fn main() {
+    let mut cli = Cli::parse();
+
+    set_json_errors(prints_json(&cli));
+
+    let cwd = current_dir().unwrap();
+    let config = get_config();
+
+    if let Some(argv) = expand_view(&cli, &config) {
+        cli = Cli::parse_from(argv);
+        set_json_errors(prints_json(&cli));
+    }
+
+    if let Some(argv) = expand_page_command(&cli) {
+        cli = Cli::parse_from(argv);
+    }
+
+    let meta_path = match resolve_setting(cli.file, "PLANNER_FILE", config.file.clone()) {
+        Some(x) if cli.list.is_none() => PathBuf::from(x),
+        _ => {
+            let list = resolve_setting(cli.list, "PLANNER_LIST", config.default_list.clone());
+            get_meta_path(&list.unwrap_or("planner".to_string()))
+        }
+    };
+
+    let no_color = cli.no_color
+        || match std::env::var("PLANNER_NO_COLOR") {
+            Ok(x) => !x.is_empty() && x != "0" && x.to_lowercase() != "false",
+            Err(_) => config.no_color.unwrap_or(false),
+        };
+
+    let date_format = resolve_setting(
+        cli.date_format,
+        "PLANNER_DATE_FORMAT",
+        config.date_format.clone(),
+    )
+    .unwrap_or(DEFAULT_DATE_FORMAT.to_string());
+
+    if let Err(e) = validate_date_format(&date_format) {
+        e.exit();
+    }
+
+    if cli.strict {
+        if let Err(e) = check_strict::<Config>(&get_config_path()) {
+            e.exit();
+        }
+
+        if let Err(e) = check_strict::<TaskList>(&meta_path) {
+            e.exit();
+        }
+    }
+
+    let mut scheme = cli.color_scheme.or(config.color_scheme).unwrap_or_default();
+
+    if no_color && scheme != ColorScheme::Mono {
+        scheme = ColorScheme::Off;
+    }
+
+    match cli.command {
+        Commands::Init(args) => {
+            let mut dir = cwd.into_os_string().into_string().unwrap();
+
+            if let Some(x) = args.dir {
+                dir = x;
+            }
+
+            if meta_path.exists() && !args.force {
+                PlannerError::Generic(
+                    "Planner is already initialized, use --force to overwrite it".to_string(),
+                )
+                .exit();
+            }
+
+            let mut initial = TaskList::default();
+
+            if let Some(x) = &args.template {
+                initial.tasks = match read_template(x) {
+                    Ok(x) => x,
+                    Err(e) => e.exit(),
+                };
+            }
+
+            if args.sample {
+                initial.tasks = sample_tasks(Local::now());
+            }
+
+            let config = if args.git {
+                save_config_value("git_autocommit", serde_json::Value::Bool(true));
+                get_config()
+            } else {
+                config
+            };
+
+            // Committed below even when the list is empty, which saving
+            // wouldn't do as nothing changed
+            let save_config = Config {
+                git_autocommit: false,
+                ..config.clone()
+            };
+
+            save_task_list(&meta_path, &initial, &save_config);
+
+            if config.git_autocommit
+                && let Err(e) = git_commit_file(&meta_path, "Initialize planner")
+            {
+                println!("Warning: {e}");
+            }
+
+            println!("Initialized planner in directory: {dir}");
+
+            if args.template.is_some() {
+                println!("Added {} tasks from template", initial.tasks.len());
+            }
+
+            if args.sample {
+                println!(
+                    "Added {} example tasks, see them with 'planner list'",
+                    initial.tasks.len()
+                );
+            }
+        }
+        Commands::Add(mut args) => {
+            let mut task_list = get_task_list(&meta_path);
+
+            if args.interactive {
+                if io::stdin().is_terminal() {
+                    prompt_add_args(&mut args, &config);
+                } else if args.taskname.is_none() || args.points.is_none() {
+                    PlannerError::Generic(
+                        "--interactive needs a terminal, give TASKNAME and --points instead"
+                            .to_string(),
+                    )
+                    .exit();
+                }
+            }
+
+            let id = args.id.unwrap_or(get_free_id(&task_list));
+
+            if let Some(src) = args.duplicate {
+                let mut copy = match duplicate_task(&task_list, src, id, Local::now()) {
+                    Ok(x) => x,
+                    Err(e) => e.exit(),
+                };
+
+                if let Some(x) = args.taskname {
+                    copy.name = x.trim().to_string();
+                }
+
+                if let Some(x) = args.points {
+                    copy.points = match parse_points(&x, config.points_scheme) {
+                        Ok(x) => x,
+                        Err(e) => e.exit(),
+                    };
+                }
+
+                check_new_name(&task_list, &copy.name, args.allow_duplicate, args.quiet);
+                check_new_due_date(copy.due_date, args.allow_past, args.quiet);
+
+                let name = copy.name.clone();
+
+                if let Err(e) = add_task(&mut task_list, copy, &config) {
+                    e.exit();
+                }
+
+                if !args.quiet {
+                    println!("Duplicated task #{src} as #{id} '{name}'");
+                }
+
+                save_task_list(&meta_path, &task_list, &config);
+
+                if args.print_id {
+                    println!("{id}");
+                }
+                return;
+            }
+
+            let taskname = args.taskname.unwrap().trim().to_string();
+
+            let points = match parse_points(&args.points.unwrap(), config.points_scheme) {
+                Ok(x) => x,
+                Err(e) => e.exit(),
+            };
+
+            check_new_name(&task_list, &taskname, args.allow_duplicate, args.quiet);
+
+            let mut deadline: Option<DateTime<Local>> = None;
+
+            if let Some(x) = args.due_date {
+                deadline = Some(get_time_from_string(x));
+            }
+
+            if let Some(x) = args.due_from_now {
+                deadline = match parse_duration(&x) {
+                    Ok(x) => Some(Local::now() + x),
+                    Err(e) => e.exit(),
+                };
+            }
+
+            check_new_due_date(deadline, args.allow_past, args.quiet);
+
+            let mut start_time: Option<DateTime<Local>> = None;
+
+            if let Some(x) = args.start_time {
+                start_time = Some(get_time_from_string(x));
+            }
+
+            if let Some(x) = args.start_from_now {
+                start_time = match parse_duration(&x) {
+                    Ok(x) => Some(Local::now() + x),
+                    Err(e) => e.exit(),
+                };
+            }
+
+            let mut depends_on: Vec<usize> = vec![];
+
+            if let Some(deps) = args.depends_on {
+                for s in deps.split(",") {
+                    let s = s.trim();
+
+                    if s.is_empty() {
+                        continue;
+                    }
+
+                    let Ok(dep) = s.parse::<usize>() else {
+                        PlannerError::Parse(format!("Invalid task id '{s}'")).exit();
+                    };
+
+                    if !depends_on.contains(&dep) {
+                        depends_on.push(dep);
+                    }
+                }
+            }
+
+            let mut new_vec: Vec<String> = vec![];
+
+            if let Some(res) = args.resources {
+                new_vec = split_comma_list(&res);
+            }
+
+            let mut tags: Vec<String> = vec![];
+
+            if let Some(x) = args.tags {
+                for tag in split_comma_list(&x) {
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+            }
+
+            let mut recurrence: Option<Recurrence> = None;
+
+            if let Some(x) = args.every {
+                let every = match parse_duration(&x) {
+                    Ok(x) => x,
+                    Err(e) => e.exit(),
+                };
+
+                recurrence = Some(Recurrence {
+                    every_seconds: every.num_seconds(),
+                    until: args.until.map(get_time_from_string),
+                    remaining: args.count,
+                    next_id: None,
+                });
+            }
+
+            let mut reminders: Vec<TimeDelta> = vec![];
+
+            for x in args.remind_before.iter() {
+                let lead = match parse_duration(x) {
+                    Ok(x) => x,
+                    Err(e) => e.exit(),
+                };
+
+                if !reminders.contains(&lead) {
+                    reminders.push(lead);
+                }
+            }
+
+            reminders.sort();
+
+            let new_task = Task::builder(id, taskname.clone(), points)
+                .reminders(reminders)
+                .recurrence(recurrence)
+                .due_date(deadline)
+                .start_time(start_time)
+                .parent(args.parent_id)
+                .resources(new_vec)
+                .tags(tags)
+                .depends_on(depends_on)
+                .priority(args.priority)
+                .build();
+
+            if let Err(e) = add_task(&mut task_list, new_task, &config) {
+                e.exit();
+            }
+
+            if !args.quiet {
+                println!("Added task '{taskname}'");
+
+                if args.parent_id.is_some() {
+                    println!("Fitting parent size to children");
+                }
+            }
+
+            save_task_list(&meta_path, &task_list, &config);
+
+            if args.print_id {
+                println!("{id}");
+            }
+        }
+        Commands::Rm(args) => {
+            let mut task_list = get_task_list(&meta_path);
+
+            let Some(task_id) = args.task_id else {
+                let filter = TaskFilter {
+                    completed: args.completed,
+                    overdue: args.overdue,
+                    tag: args.tag.clone(),
+                };
+
+                let now = Local::now();
+                let grace = overdue_threshold(None, &config);
+
+                let matching: Vec<usize> = task_list
+                    .tasks
+                    .iter()
+                    .filter(|x| filter.matches(x, now, grace))
+                    .map(|x| x.id)
+                    .collect();
+
+                if matching.is_empty() {
+                    println!("No tasks match");
+                    return;
+                }
+
+                let broken = broken_dependencies(&task_list, &matching);
+
+                if args.dry_run {
+                    println!("Would remove {} tasks:", matching.len());
+
+                    for id in matching.iter() {
+                        let idx = get_task_index(&task_list, *id).unwrap();
+                        println!("  #{id} '{}'", task_list.tasks[idx].name);
+                    }
+
+                    print_broken_dependencies(&task_list, &broken, "would lose");
+                    return;
+                }
+
+                if !broken.is_empty() && !args.force {
+                    print_broken_dependencies(&task_list, &broken, "would lose");
+
+                    PlannerError::Generic(
+                        "Other tasks depend on these tasks, use --force to remove them anyway"
+                            .to_string(),
+                    )
+                    .exit();
+                }
+
+                println!("This will remove {} tasks", matching.len());
+
+                if !args.yes && !confirm("Continue?") {
+                    PlannerError::Generic("Nothing removed, pass --yes to skip asking".to_string())
+                        .exit();
+                }
+
+                print_broken_dependencies(&task_list, &broken, "lost");
+
+                if let Err(e) = remove_tasks(&mut task_list, &matching, args.force) {
+                    e.exit();
+                }
+
+                save_task_list(&meta_path, &task_list, &config);
+
+                println!("Removed {} tasks", matching.len());
+                return;
+            };
+
+            let Some(idx) = get_task_index(&task_list, task_id) else {
+                PlannerError::TaskNotFound(task_id).exit();
+            };
+
+            let broken = broken_dependencies(&task_list, &[task_id]);
+
+            if args.dry_run {
+                println!("Would remove task '{}'", task_list.tasks[idx].name);
+                print_broken_dependencies(&task_list, &broken, "would lose");
+                return;
+            }
+
+            let before = task_list.clone();
+
+            let removed = match remove_tasks(&mut task_list, &[task_id], args.force) {
+                Ok(x) => x,
+                Err(PlannerError::Generic(msg)) => {
+                    print_broken_dependencies(&task_list, &broken, "would lose");
+                    PlannerError::Generic(format!("{msg}, use --force to remove it anyway")).exit();
+                }
+                Err(e) => e.exit(),
+            };
+
+            print_broken_dependencies(&before, &broken, "lost");
+
+            save_task_list(&meta_path, &task_list, &config);
+
+            println!("Removed task '{}'", removed[0].name)
+        }
+        Commands::Check(args) => {
+            let mut task_list = get_task_list(&meta_path);
+
+            let mut completed_at = Local::now();
+
+            if let Some(x) = args.at {
+                completed_at = get_time_from_string(x);
+
+                if completed_at > Local::now() {
+                    PlannerError::Generic("Completion time can't be in the future".to_string())
+                        .exit();
+                }
+            }
+
+            let mut reopen_at: Option<DateTime<Local>> = None;
+
+            if let Some(x) = &args.undo_after {
+                reopen_at = match parse_duration(x) {
+                    Ok(x) => Some(Local::now() + x),
+                    Err(e) => e.exit(),
+                };
+            }
+
+            let Some(task_id) = args.task_id else {
+                let filter = TaskFilter {
+                    overdue: args.overdue,
+                    tag: args.tag.clone(),
+                    ..Default::default()
+                };
+
+                let now = Local::now();
+                let grace = overdue_threshold(None, &config);
+
+                let ids: Vec<usize> = task_list
+                    .tasks
+                    .iter()
+                    .filter(|x| !x.complete && filter.matches(x, now, grace))
+                    .map(|x| x.id)
+                    .collect();
+
+                if ids.is_empty() {
+                    println!("No tasks match");
+                    return;
+                }
+
+                let (count, skipped, cascaded) = check_tasks(
+                    &mut task_list,
+                    &ids,
+                    completed_at,
+                    args.note.clone(),
+                    reopen_at,
+                    &config,
+                );
+
+                if count > 0 {
+                    save_task_list(&meta_path, &task_list, &config);
+                }
+
+                println!("Checked off {count} tasks");
+
+                for id in cascaded {
+                    let idx = get_task_index(&task_list, id).unwrap();
+
+                    println!("Also checked off task '{}'", task_list.tasks[idx].name);
+                }
+
+                for id in skipped {
+                    let idx = get_task_index(&task_list, id).unwrap();
+
+                    println!(
+                        "Skipped '{}', it waits on open dependencies or subtasks",
+                        task_list.tasks[idx].name
+                    );
+                }
+
+                return;
+            };
+
+            let outcome = match check_task(
+                &mut task_list,
+                task_id,
+                completed_at,
+                args.note,
+                reopen_at,
+                args.close_deps,
+                &config,
+            ) {
+                Ok(x) => x,
+                Err(e) => e.exit(),
+            };
+
+            let idx = get_task_index(&task_list, task_id).unwrap();
+            let name = task_list.tasks[idx].name.clone();
+
+            save_task_list(&meta_path, &task_list, &config);
+
+            println!("Checked off task '{name}'");
+
+            if outcome.bonus > 0 {
+                println!(
+                    "{} days in a row, +{} bonus points",
+                    completion_streak(&task_list, completed_at),
+                    outcome.bonus
+                );
+            }
+
+            if outcome.penalty > 0 {
+                println!("Completed after its due date, -{} points", outcome.penalty);
+            }
+
+            if let Some(x) = reopen_at {
+                println!("It will reopen on {}", x.format(&date_format));
+            }
+
+            for id in outcome.closed {
+                let idx = get_task_index(&task_list, id).unwrap();
+
+                println!(
+                    "Also checked off dependency '{}'",
+                    task_list.tasks[idx].name
+                );
+            }
+
+            for id in outcome.left_open {
+                let idx = get_task_index(&task_list, id).unwrap();
+
+                println!(
+                    "Left dependency '{}' open, it waits on open dependencies or subtasks",
+                    task_list.tasks[idx].name
+                );
+            }
+
+            for id in outcome.cascaded {
+                let idx = get_task_index(&task_list, id).unwrap();
+
+                println!("Also checked off task '{}'", task_list.tasks[idx].name);
+            }
+
+            if let Some(x) = outcome.next {
+                println!(
+                    "Next occurrence is #{} due on {}",
+                    x.id,
+                    x.due_date.unwrap().format(&date_format)
+                );
+            }
+        }
+        Commands::Uncheck(args) => {
+            let mut task_list = get_task_list(&meta_path);
+
+            if let Err(e) = uncheck_task(&mut task_list, args.task_id) {
+                e.exit();
+            }
+
+            save_task_list(&meta_path, &task_list, &config);
+
+            let idx = get_task_index(&task_list, args.task_id).unwrap();
+
+            println!("Unchecked task '{}'", task_list.tasks[idx].name)
+        }
+        Commands::Mv(args) => {
+            let from_path = match args.from {
+                Some(x) => get_meta_path(&x),
+                None => meta_path,
+            };
+            let to_path = get_meta_path(&args.to);
+
+            if from_path == to_path {
+                PlannerError::Generic("Source and destination lists are the same".to_string())
+                    .exit();
+            }
+
+            let mut from_list = get_task_list(&from_path);
+            let mut to_list = get_task_list(&to_path);
+            let old_to_list = to_list.clone();
+
+            let task = match move_task_to_list(&mut from_list, &mut to_list, args.task_id) {
+                Ok(x) => x,
+                Err(e) => e.exit(),
+            };
+
+            // Write the destination first so the task is never only in memory,
+            // then undo that write if the source can't be updated
+            save_task_list(&to_path, &to_list, &config);
+
+            match write_task_list(&from_path, &from_list, &config) {
+                Ok(warnings) => {
+                    for x in warnings {
+                        println!("Warning: {x}");
+                    }
+                }
+                Err(e) => {
+                    save_task_list(&to_path, &old_to_list, &config);
+                    PlannerError::Generic(format!("Could not write to file: {e}")).exit();
+                }
+            }
+
+            println!(
+                "Moved task '{}' from {} #{} to {} #{}",
+                task.name,
+                from_path.file_stem().unwrap().to_string_lossy(),
+                args.task_id,
+                args.to,
+                task.id
+            );
+        }
+        Commands::Stats(args) => {
+            let task_list = get_task_list(&meta_path);
+
+            let stats = compute_stats(
+                &task_list,
+                Local::now(),
+                overdue_threshold(None, &config),
+                args.by_tag,
+            );
+
+            if args.json {
+                println!("{}", serde_json::to_string(&stats).unwrap());
+                return;
+            }
+
+            println!("Tasks: {} ({} completed)", stats.tasks, stats.completed);
+
+            if args.remaining_only {
+                println!("{}", remaining_summary(&task_list, &config, scheme));
+            } else {
+                let msg = format!(
+                    "Points: {}/{} ({}%){}",
+                    stats.points_earned - stats.points_bonus,
+                    stats.points_total,
+                    stats.percent,
+                    bonus_suffix(stats.points_bonus)
+                );
+
+                println!(
+                    "{}",
+                    colorize(&msg, summary_status(stats.percent, &config), scheme)
+                );
+            }
+
+            if let Some(x) = budget_warning(&config, stats.points_open, scheme) {
+                println!("{x}");
+            }
+
+            if let Some(by_tag) = &stats.by_tag {
+                println!();
+                println!("By tag:");
+
+                for (tag, tag_stats) in by_tag.iter() {
+                    if tag != "(untagged)" {
+                        println!("{}", tag_stats.describe(tag));
+                    }
+                }
+
+                if let Some(x) = by_tag.get("(untagged)") {
+                    println!("{}", x.describe("(untagged)"));
+                }
+            }
+        }
+        Commands::Export(args) => {
+            let task_list = get_task_list(&meta_path);
+
+            let out = match args.format {
+                ExportFormat::Html => {
+                    let grace = overdue_threshold(None, &config);
+
+                    generate_html(&task_list, Local::now(), grace, &config, &date_format)
+                }
+                ExportFormat::Jsonl => generate_jsonl(&task_list),
+                #[cfg(feature = "chart")]
+                ExportFormat::Png => {
+                    let Some(x) = args.output else {
+                        PlannerError::Generic(
+                            "Give a file to write the PNG to with --output".to_string(),
+                        )
+                        .exit();
+                    };
+
+                    if let Err(e) = write_png_chart(&task_list, args.chart, Path::new(&x)) {
+                        e.exit();
+                    }
+
+                    println!("Exported chart to {x}");
+                    return;
+                }
+                #[cfg(feature = "chart")]
+                ExportFormat::Svg => match generate_svg_chart(&task_list, args.chart) {
+                    Ok(x) => x,
+                    Err(e) => e.exit(),
+                },
+            };
+
+            if let Some(x) = args.output {
+                write_output(&x, out);
+
+                println!("Exported tasks to {x}");
+            } else {
+                print!("{out}");
+            }
+        }
+        Commands::Completions(args) => {
+            let mut cmd = Cli::command();
+
+            if args.check {
+                let mut problems: Vec<String> = vec![];
+
+                cmd.build();
+                check_command_tree(&cmd, "planner", &mut problems);
+
+                for x in problems.iter() {
+                    println!("{x}");
+                }
+
+                if !problems.is_empty() {
+                    PlannerError::Generic(format!("Found {} problems", problems.len())).exit();
+                }
+
+                println!("All commands and flags have help text and unique names");
+                return;
+            }
+
+            clap_complete::generate(args.shell.unwrap(), &mut cmd, "planner", &mut io::stdout());
+        }
+        Commands::View(args) => match args.action {
+            Some(ViewAction::Save { name, args }) => {
+                if args
+                    .iter()
+                    .any(|x| x == "--view" || x.starts_with("--view="))
+                {
+                    PlannerError::Generic("A view can't include another view".to_string()).exit();
+                }
+
+                let mut argv = vec!["planner".to_string(), "list".to_string()];
+                argv.extend(args.iter().cloned());
+
+                if let Err(e) = Cli::try_parse_from(argv) {
+                    PlannerError::Parse(format!("Invalid flags for the view: {e}")).exit();
+                }
+
+                let mut views = config.views.clone();
+                views.insert(name.clone(), args);
+
+                save_config_value("views", serde_json::to_value(views).unwrap());
+
+                println!("Saved view '{name}'");
+            }
+            Some(ViewAction::Rm { name }) => {
+                let mut views = config.views.clone();
+
+                if views.remove(&name).is_none() {
+                    PlannerError::Generic(format!("No view named '{name}'")).exit();
+                }
 
-    #[arg(help = "How many points the task should reward")]
-    #[arg(long, short)]
-    points: u32,
+                save_config_value("views", serde_json::to_value(views).unwrap());
 
-    #[arg(help = "Due date of the task, given in the format 'yyyy-mm-dd HH:MM:SS'")]
-    #[arg(long)]
-    due_date: Option<String>,
+                println!("Removed view '{name}'");
+            }
+            Some(ViewAction::Ls) => {
+                for (name, flags) in config.views.iter() {
+                    println!("{name}: {}", flags.join(" "));
+                }
+            }
+            None => {
+                PlannerError::Generic("Give the name of a view to show".to_string()).exit();
+            }
+        },
+        Commands::Import(args) => {
+            if let Some(path) = args.from {
+                let other = match read_task_list(Path::new(&path)) {
+                    Ok(x) => x,
+                    Err(PlannerError::NotInitialized) => {
+                        PlannerError::Generic(format!("Could not find '{path}'")).exit();
+                    }
+                    Err(e) => e.exit(),
+                };
 
-    #[arg(help = "Start time of the task, given in the format 'yyyy-mm-dd HH:MM:SS'")]
-    #[arg(long)]
-    start_time: Option<String>,
+                let mut task_list = get_task_list(&meta_path);
 
-    #[arg(help = "A number representing how important the task is to complete")]
-    #[arg(long, default_value_t = 0)]
-    priority: u8,
+                let before = task_list.tasks.len();
+                let report = merge_task_list(&mut task_list, other);
+                let added = task_list.tasks.len() - before;
 
-    #[arg(help = "The id of the parent of this task")]
-    #[arg(long)]
-    parent_id: Option<usize>,
+                save_task_list(&meta_path, &task_list, &config);
 
-    #[arg(help = "A comma-separated list of resources to allocate to this task")]
-    #[arg(long, short)]
-    resources: Option<String>,
-}
+                for line in report.iter() {
+                    println!("{line}");
+                }
 
-#[derive(Args)]
-struct RmArgs {
-    #[arg(help = "The id of the task")]
-    task_id: usize,
-}
+                println!(
+                    "Merged {added} tasks from {path}, skipped {}",
+                    report.len() - added
+                );
+                return;
+            }
 
-#[derive(Args)]
-struct CheckArgs {
-    #[arg(help = "The id of the task")]
-    task_id: usize,
-}
+            let input = args.input.unwrap();
 
-use serde::{Deserialize, Serialize};
+            let Ok(raw) = fs::read_to_string(&input) else {
+                PlannerError::Generic(format!("Could not read '{input}'")).exit();
+            };
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct TaskList {
-    tasks: Vec<Task>,
-}
+            let tasks = match args.format.unwrap() {
+                ImportFormat::Jsonl => parse_jsonl_tasks(&raw),
+            };
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct Task {
-    name: String,
-    points: u32,
-    id: usize,
-    complete: bool,
-    due_date: Option<DateTime<Local>>,
-    start_time: Option<DateTime<Local>>,
-    parent: Option<usize>,
-    resources: Vec<String>,
-}
+            let tasks = match tasks {
+                Ok(x) => x,
+                Err(e) => e.exit(),
+            };
 
-fn get_task_list() -> TaskList {
-    let cwd = current_dir().unwrap();
-    let mut meta_path = cwd.clone();
-    meta_path.push("planner");
-    meta_path.set_extension("json");
+            let mut task_list = get_task_list(&meta_path);
 
-    if !meta_path.exists() {
-        println!("Meta file does not exist, use 'planner init' to create it");
-        exit(1);
-    }
+            let count = tasks.len();
+            let before = task_list.tasks.len();
+            let report = merge_tasks(&mut task_list, tasks, args.merge);
+            let added = task_list.tasks.len() - before;
 
-    let raw_file = fs::read_to_string(meta_path.clone()).unwrap();
+            save_task_list(&meta_path, &task_list, &config);
 
-    let task_list: TaskList = serde_json::from_str(raw_file.as_str()).unwrap();
+            for line in report {
+                println!("{line}");
+            }
 
-    return task_list;
-}
+            match args.merge {
+                MergePolicy::Skip if count > added => println!(
+                    "Imported {added} tasks from {input}, skipped {}",
+                    count - added
+                ),
+                MergePolicy::Replace if count > added => println!(
+                    "Imported {added} tasks from {input}, replaced {}",
+                    count - added
+                ),
+                _ => println!("Imported {added} tasks from {input}"),
+            }
+        }
+        Commands::Progress(args) => {
+            let mut task_list = get_task_list(&meta_path);
 
-fn get_time_from_string(date: String) -> DateTime<Local> {
-    let native = NaiveDateTime::parse_from_str(date.as_str(), "%Y-%m-%d %H:%M:%S").unwrap();
-    let actual: DateTime<Local> = Local.from_local_datetime(&native).unwrap();
+            let next = match set_progress(&mut task_list, args.task_id, args.percent, Local::now())
+            {
+                Ok(x) => x,
+                Err(e) => e.exit(),
+            };
 
-    return actual;
-}
+            save_task_list(&meta_path, &task_list, &config);
 
-fn get_all_children_of_task(tasklist: &TaskList, parent: usize) -> Vec<Task> {
-    let mut children: Vec<Task> = vec![];
+            let idx = get_task_index(&task_list, args.task_id).unwrap();
 
-    for i in 0..tasklist.tasks.len() {
-        if let Some(x) = tasklist.tasks[i].parent {
-            if x == parent {
-                children.push(tasklist.tasks[i].clone());
+            println!(
+                "Task '{}' is {}% done",
+                task_list.tasks[idx].name, args.percent
+            );
+
+            if let Some(x) = next {
+                println!(
+                    "Next occurrence is #{} due on {}",
+                    x.id,
+                    x.due_date.unwrap().format(&date_format)
+                );
             }
         }
-    }
+        Commands::SetPointsScheme(args) => {
+            let value = serde_json::to_value(args.scheme).unwrap();
 
-    return children;
-}
+            save_config_value("points_scheme", value);
 
-fn task_has_children(tasklist: &TaskList, id: usize) -> bool {
-    for i in 0..tasklist.tasks.len() {
-        if tasklist.tasks[i].id == id {
-            return get_all_children_of_task(&tasklist, i).len() > 0;
+            println!(
+                "Points are now written as {}",
+                args.scheme.to_possible_value().unwrap().get_name()
+            );
         }
-    }
+        Commands::Edit(args) => {
+            let mut task_list = get_task_list(&meta_path);
 
-    return false;
-}
+            let Some(idx) = get_task_index(&task_list, args.task_id) else {
+                PlannerError::TaskNotFound(args.task_id).exit();
+            };
+
+            let mut task = task_list.tasks[idx].clone();
+
+            if args.interactive {
+                task = match edit_task_in_editor(&task) {
+                    Ok(x) => x,
+                    Err(e) => e.exit(),
+                };
+            } else {
+                if let Some(x) = args.name {
+                    task.name = x.trim().to_string();
+                }
+
+                if let Some(x) = args.points {
+                    task.points = match parse_points(&x, config.points_scheme) {
+                        Ok(x) => x,
+                        Err(e) => e.exit(),
+                    };
+                }
 
-fn get_start_and_end_of_children(
-    children: Vec<Task>,
-) -> (Option<DateTime<Local>>, Option<DateTime<Local>>) {
-    let mut start_time: Option<DateTime<Local>> = None;
-    let mut end_time: Option<DateTime<Local>> = None;
+                if let Some(x) = args.due_date {
+                    task.due_date = Some(get_time_from_string(x));
+                }
+            }
 
-    for i in 0..children.len() {
-        if let Some(x) = children[i].start_time {
-            if start_time == None || x < start_time.unwrap() {
-                start_time = Some(x);
+            if let Err(e) = validate_task(&task_list, &task, &config) {
+                e.exit();
             }
+
+            task_list.tasks[idx] = task;
+
+            save_task_list(&meta_path, &task_list, &config);
+
+            println!("Edited task '{}'", task_list.tasks[idx].name);
         }
-        if let Some(x) = children[i].due_date {
-            if end_time == None || x > end_time.unwrap() {
-                end_time = Some(x);
+        Commands::Version(args) => {
+            let mut list_version: Option<u32> = None;
+
+            // Read the field on its own so this works for lists this version
+            // can't load
+            if args.data
+                && let Ok(raw) = fs::read_to_string(&meta_path)
+                && let Ok(x) = serde_json::from_str::<serde_json::Value>(&raw)
+            {
+                list_version = x["version"].as_u64().map(|x| x as u32);
             }
-        }
-    }
 
-    return (start_time, end_time);
-}
+            if args.json {
+                let mut out = serde_json::json!({
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "schema_version": SCHEMA_VERSION,
+                });
 
-fn fit_task_size_to_children(
-    tasklist: &mut TaskList,
-    id: usize,
-) -> (Option<DateTime<Local>>, Option<DateTime<Local>>) {
-    println!("{id}");
+                if args.data {
+                    out["list_version"] = serde_json::json!(list_version);
+                }
 
-    let mut children = get_all_children_of_task(tasklist, id);
+                println!("{out}");
+                return;
+            }
 
-    let mut start_time: Option<DateTime<Local>> = None;
-    let mut end_time: Option<DateTime<Local>> = None;
+            println!("planner {}", env!("CARGO_PKG_VERSION"));
+            println!("Schema version: {SCHEMA_VERSION}");
 
-    for i in 0..children.len() {
-        let mut dates = (children[i].start_time, children[i].due_date);
+            if args.data {
+                let name = meta_path.file_name().unwrap().to_string_lossy();
 
-        if task_has_children(tasklist, children[i].id) {
-            dates = fit_task_size_to_children(tasklist, children[i].id);
-            for j in 0..tasklist.tasks.len() {
-                if tasklist.tasks[j].id == children[i].id {
-                    tasklist.tasks[j].start_time = dates.0;
-                    tasklist.tasks[j].due_date = dates.1;
+                if !meta_path.exists() {
+                    println!("List '{name}': not found");
+                } else if let Some(x) = list_version {
+                    println!("List '{name}': schema version {x}");
+                } else {
+                    println!("List '{name}': no schema version");
                 }
             }
         }
+        Commands::Due(args) => {
+            let mut task_list = get_task_list(&meta_path);
+
+            let Some(idx) = get_task_index(&task_list, args.task_id) else {
+                PlannerError::TaskNotFound(args.task_id).exit();
+            };
+
+            task_list.tasks[idx].due_date = args.date.map(get_time_from_string);
 
-        if let Some(x) = dates.0 {
-            if start_time == None || x < start_time.unwrap() {
-                start_time = Some(x);
+            save_task_list(&meta_path, &task_list, &config);
+
+            let task = &task_list.tasks[idx];
+
+            match task.due_date {
+                Some(x) => println!("Task '{}' is due on {}", task.name, x.format(&date_format)),
+                None => println!("Task '{}' has no due date", task.name),
             }
         }
-        if let Some(x) = dates.1 {
-            if end_time == None || x > end_time.unwrap() {
-                end_time = Some(x);
+        Commands::Snooze(args) => {
+            let mut task_list = get_task_list(&meta_path);
+
+            let delay = match parse_duration(&args.by) {
+                Ok(x) => x,
+                Err(e) => e.exit(),
+            };
+
+            let now = Local::now();
+            let grace = overdue_threshold(None, &config);
+
+            let ids: Vec<usize> = match args.task_id {
+                Some(id) => {
+                    let Some(idx) = get_task_index(&task_list, id) else {
+                        PlannerError::TaskNotFound(id).exit();
+                    };
+
+                    if task_list.tasks[idx].due_date.is_none() {
+                        PlannerError::Generic(format!(
+                            "Task '{}' has no due date to snooze",
+                            task_list.tasks[idx].name
+                        ))
+                        .exit();
+                    }
+
+                    vec![id]
+                }
+                None => {
+                    let filter = TaskFilter {
+                        overdue: args.overdue,
+                        tag: args.tag.clone(),
+                        ..Default::default()
+                    };
+
+                    task_list
+                        .tasks
+                        .iter()
+                        .filter(|x| !x.complete && x.due_date.is_some())
+                        .filter(|x| filter.matches(x, now, grace))
+                        .map(|x| x.id)
+                        .collect()
+                }
+            };
+
+            snooze_tasks(&mut task_list, &ids, delay);
+
+            for id in ids.iter() {
+                let task = &task_list.tasks[get_task_index(&task_list, *id).unwrap()];
+
+                println!(
+                    "Task '{}' is now due on {}",
+                    task.name,
+                    task.due_date.unwrap().format(&date_format)
+                );
+            }
+
+            if !ids.is_empty() {
+                save_task_list(&meta_path, &task_list, &config);
             }
+
+            println!("Snoozed {} tasks by {}", ids.len(), args.by);
         }
-    }
+        Commands::Bump(args) => {
+            let mut task_list = get_task_list(&meta_path);
+
+            let (old, new) = match bump_points(&mut task_list, args.task_id, args.delta, &config) {
+                Ok(x) => x,
+                Err(e) => e.exit(),
+            };
+
+            save_task_list(&meta_path, &task_list, &config);
 
-    for i in 0..tasklist.tasks.len() {
-        if tasklist.tasks[i].id == id {
-            tasklist.tasks[i].start_time = start_time;
-            tasklist.tasks[i].due_date = end_time;
+            let idx = get_task_index(&task_list, args.task_id).unwrap();
+
+            println!(
+                "Task '{}' is now worth {new} points (was {old})",
+                task_list.tasks[idx].name
+            )
         }
-    }
+        Commands::Replay(args) => {
+            let log_path = match args.log {
+                Some(x) => PathBuf::from(x),
+                None => meta_path.with_extension("log"),
+            };
 
-    return (start_time, end_time);
-}
+            let Ok(raw) = fs::read_to_string(&log_path) else {
+                PlannerError::Generic(format!("Could not read audit log '{}'", log_path.display()))
+                    .exit();
+            };
 
-fn print_task(i: &Task, indent: u8) {
-    for _i in 0..indent {
-        print!("  ");
-    }
+            let (task_list, warnings) = match replay_audit_log(&raw) {
+                Ok(x) => x,
+                Err(e) => e.exit(),
+            };
 
-    let mut msg = format!("#{} {} ({} points)", i.id, i.name, i.points);
+            for warning in warnings.iter() {
+                println!("Warning: {warning}");
+            }
 
-    if i.due_date == None && i.start_time != None {
-        msg += format!(
-            "  Start work on {}",
-            i.start_time.unwrap().format("%Y-%m-%d at %H:%M:%S")
-        )
-        .as_str();
-    } else if i.start_time == None && i.due_date != None {
-        msg += format!(
-            "  Due on {}",
-            i.due_date.unwrap().format("%Y-%m-%d at %H:%M:%S")
-        )
-        .as_str();
-    } else if i.start_time != None && i.due_date != None {
-        msg += format!(
-            "  Start work on {} and end on {}",
-            i.start_time.unwrap().format("%Y-%m-%d at %H:%M:%S"),
-            i.due_date.unwrap().format("%Y-%m-%d at %H:%M:%S")
-        )
-        .as_str();
-    }
+            write_output(&args.output, serde_json::to_string(&task_list).unwrap());
 
-    if i.resources.len() > 0 {
-        msg += "\n";
-        for _i in 0..indent {
-            msg += "  ";
+            println!(
+                "Rebuilt {} tasks into {}",
+                task_list.tasks.len(),
+                args.output
+            );
         }
+        Commands::Diff(args) => {
+            let task_list = get_task_list(&meta_path);
 
-        msg += format!("Required resources: {:?}", i.resources).as_str();
-    }
+            let snapshot_path = match args.snapshot {
+                Some(x) => PathBuf::from(x),
+                None => get_backup_path(&meta_path),
+            };
 
-    if i.complete {
-        println!("\x1b[32m{msg}\x1b[0m");
-    } else {
-        println!("{msg}");
-    }
-}
+            let Ok(raw) = fs::read_to_string(&snapshot_path) else {
+                PlannerError::Generic(format!(
+                    "Could not read snapshot '{}'",
+                    snapshot_path.display()
+                ))
+                .exit();
+            };
 
-#[derive(Debug, Clone)]
-struct TaskTreeNode {
-    task: Option<usize>,
-    children: Vec<usize>,
-}
+            let snapshot = match parse_json_tasks(&raw) {
+                Ok(tasks) => TaskList {
+                    tasks,
+                    ..Default::default()
+                },
+                Err(e) => e.exit(),
+            };
+
+            print!("{}", render_task_list_diff(&snapshot, &task_list, scheme));
+        }
+        Commands::Tidy(args) => {
+            let Some(age) = args.older_than.or(config.auto_archive_after.clone()) else {
+                PlannerError::Generic(
+                    "No age given, use --older-than or set auto_archive_after".to_string(),
+                )
+                .exit();
+            };
 
-fn generate_task_tree(tasklist: &TaskList) -> Vec<TaskTreeNode> {
-    let mut tree = vec![TaskTreeNode {
-        task: None,
-        children: vec![],
-    }];
+            let max_age = match parse_duration(&age) {
+                Ok(x) => x,
+                Err(e) => e.exit(),
+            };
 
-    let mut queue: Vec<usize> = (0..tasklist.tasks.len()).collect();
+            let mut task_list = get_task_list(&meta_path);
 
-    loop {
-        if queue.len() == 0 {
-            break;
+            let reopened = reopen_due_tasks(&mut task_list, Local::now());
+            let count =
+                match archive_completed_tasks(&meta_path, &mut task_list, max_age, Local::now()) {
+                    Ok(x) => x,
+                    Err(e) => e.exit(),
+                };
+
+            if count > 0 || !reopened.is_empty() {
+                save_task_list(&meta_path, &task_list, &config);
+            }
+
+            for name in reopened.iter() {
+                println!("Reopened task '{name}'");
+            }
+
+            println!("Archived {count} tasks completed more than {age} ago");
+        }
+        Commands::Remind => {
+            let task_list = get_task_list(&meta_path);
+            let now = Local::now();
+
+            for task in task_list.tasks.iter() {
+                if active_reminder(task, now).is_some() {
+                    println!(
+                        "Task #{} '{}' is due in {}",
+                        task.id,
+                        task.name,
+                        humanize_duration(task.due_date.unwrap() - now)
+                    );
+                }
+            }
         }
+        Commands::Config(args) => match args.action {
+            ConfigAction::Edit => {
+                let Ok(editor) = std::env::var("EDITOR") else {
+                    println!("$EDITOR is not set, the config file is at:");
+                    println!("{}", get_config_path().display());
+                    return;
+                };
+
+                if let Err(e) = edit_config_in_editor(&editor) {
+                    e.exit();
+                }
 
-        let t = queue.pop().unwrap();
+                println!("Saved {}", get_config_path().display());
+            }
+        },
+        Commands::Tag(args) => {
+            let mut task_list = get_task_list(&meta_path);
+
+            let (tag, count) = match &args.action {
+                TagAction::Rename { old, new } => {
+                    if new.trim() == "" {
+                        PlannerError::Generic("A tag can't be empty".to_string()).exit();
+                    }
 
-        let mut proc = false;
+                    (old, rename_tag(&mut task_list, old, new.trim()))
+                }
+                TagAction::Rm { tag } => (tag, remove_tag(&mut task_list, tag)),
+            };
 
-        for i in 0..tree.len() {
-            if tree[i].task == tasklist.tasks[t].parent {
-                let l = tree.len();
+            if count == 0 {
+                PlannerError::Generic(format!("No task is tagged '{tag}'")).exit();
+            }
 
-                tree[i].children.push(l);
-                tree.push(TaskTreeNode {
-                    task: Some(tasklist.tasks[t].id),
-                    children: vec![],
-                });
+            save_task_list(&meta_path, &task_list, &config);
 
-                proc = true;
-                break;
+            match args.action {
+                TagAction::Rename { old, new } => {
+                    println!("Renamed tag '{old}' to '{}' on {count} tasks", new.trim())
+                }
+                TagAction::Rm { tag } => println!("Removed tag '{tag}' from {count} tasks"),
             }
         }
+        Commands::Tags => {
+            let task_list = get_task_list(&meta_path);
 
-        if !proc {
-            queue.insert(0, t);
+            for (tag, count) in count_tags(&task_list) {
+                println!("{tag}: {count}");
+            }
         }
-    }
+        Commands::Snapshots => {
+            let snapshots = list_snapshots(&meta_path);
 
-    return tree;
-}
+            if snapshots.is_empty() {
+                println!("No snapshots taken");
+                return;
+            }
 
-fn print_task_tree(tasklist: &TaskList, tree: Vec<TaskTreeNode>, depth: u8, idx: usize) {
-    let r = tree[idx].clone();
+            for (i, (stamp, path)) in snapshots.iter().enumerate() {
+                let tasks = get_task_list(path).tasks.len();
 
-    let mut depth_add = 0;
+                println!("{i}: {stamp} ({tasks} tasks)");
+            }
+        }
+        Commands::Restore(args) => {
+            let snapshots = list_snapshots(&meta_path);
 
-    if let Some(x) = r.task {
-        print_task(&tasklist.tasks[x], depth);
-        depth_add = 1;
-    }
+            let found = match args.snapshot.parse::<usize>() {
+                Ok(i) => snapshots.get(i),
+                Err(_) => snapshots.iter().find(|x| x.0 == args.snapshot),
+            };
+
+            let Some((stamp, path)) = found else {
+                PlannerError::Generic(format!("No snapshot '{}'", args.snapshot)).exit();
+            };
+
+            let snapshot = get_task_list(path);
 
-    if r.children.len() > 0 {
-        for i in r.children {
-            print_task_tree(&tasklist, tree.clone(), depth + depth_add, i);
+            if !args.yes && !confirm(format!("Restore the list as it was at {stamp}?").as_str()) {
+                PlannerError::Generic("Nothing restored, pass --yes to skip asking".to_string())
+                    .exit();
+            }
+
+            // Saving takes a snapshot of the current list, so this can be
+            // rolled back as well
+            save_task_list(&meta_path, &snapshot, &config);
+
+            println!(
+                "Restored {} tasks from the snapshot at {stamp}",
+                snapshot.tasks.len()
+            );
         }
-    }
-}
+        // Rewritten to `list` before dispatching
+        Commands::NextPage(_) | Commands::PrevPage(_) => unreachable!(),
 
-fn main() {
-    let cli = Cli::parse();
+        Commands::WatchDue(args) => {
+            let interval = match parse_duration(&args.interval) {
+                Ok(x) => x,
+                Err(e) => e.exit(),
+            };
 
-    let cwd = current_dir().unwrap();
-    let mut meta_path = cwd.clone();
-    meta_path.push("planner");
-    meta_path.set_extension("json");
+            watch_due(&meta_path, interval);
+        }
+        Commands::Up(args) => move_task_command(&meta_path, args.task_id, true, &config),
+        Commands::Down(args) => move_task_command(&meta_path, args.task_id, false, &config),
+        Commands::Renumber(args) => {
+            let mut task_list = get_task_list(&meta_path);
 
-    match cli.command {
-        Commands::Init(args) => {
-            let mut dir = cwd.into_os_string().into_string().unwrap();
+            let mut ids: Vec<usize> = task_list.tasks.iter().map(|x| x.id).collect();
+            ids.sort();
 
-            if let Some(x) = args.dir {
-                dir = x;
+            let changed = ids.iter().enumerate().filter(|(i, x)| i != *x).count();
+
+            if changed == 0 {
+                println!("Task ids have no gaps");
+                return;
             }
 
-            let initial = TaskList { tasks: vec![] };
+            if !args.yes && !confirm(format!("Change the id of {changed} tasks?").as_str()) {
+                PlannerError::Generic("Nothing renumbered, pass --yes to skip asking".to_string())
+                    .exit();
+            }
 
-            fs::write(meta_path, serde_json::to_string(&initial).unwrap())
-                .expect("Could not write to file");
+            remap_ids(&mut task_list, &ids, 0);
 
-            println!("Initialized planner in directory: {dir}");
+            save_task_list(&meta_path, &task_list, &config);
+
+            println!("Renumbered {changed} tasks");
         }
-        Commands::Add(args) => {
-            let mut task_list = get_task_list();
+        Commands::Goal(args) => {
+            let Some(goal) = args.target.or(config.points_goal) else {
+                PlannerError::Generic(
+                    "No goal set, use --target or set points_goal in the config".to_string(),
+                )
+                .exit();
+            };
 
-            let mut id = 0 as usize;
+            let task_list = get_task_list(&meta_path);
 
-            loop {
-                let mut found = false;
+            let since = get_period_start(args.period, Local::now(), config.week_start);
+            let earned = points_earned_since(&task_list, since);
+            let perc = completion_percentage(earned, goal as u64).min(100);
 
-                for i in 0..task_list.tasks.len() {
-                    if id == task_list.tasks[i].id {
-                        found = true;
-                        break;
-                    }
-                }
+            let label = match args.period {
+                Period::Day => "Today",
+                Period::Week => "This week",
+                Period::Month => "This month",
+            };
 
-                if !found {
-                    break;
-                }
+            println!(
+                "{label}: {earned} of {goal} points {} {perc}%",
+                progress_bar(perc as u8)
+            );
 
-                id += 1;
+            if earned >= goal as u64 {
+                println!("{}", colorize("Goal reached!", Status::Good, scheme));
+            } else {
+                println!("{} to go", goal as u64 - earned);
+            }
+        }
+        Commands::Report(args) => {
+            let task_list = get_task_list(&meta_path);
+
+            let report = generate_report(
+                &task_list,
+                args.period,
+                Local::now(),
+                overdue_threshold(None, &config),
+                config.week_start,
+                &date_format,
+            );
+
+            if let Some(x) = args.output {
+                write_output(&x, report);
+
+                println!("Wrote report to {x}");
+            } else {
+                print!("{report}");
+            }
+        }
+        Commands::List(args) => {
+            if args.json_schema {
+                println!("{}", task_list_schema());
+                return;
             }
 
-            let mut deadline: Option<DateTime<Local>> = None;
+            let mut task_list = get_task_list(&meta_path);
 
-            if let Some(x) = args.due_date {
-                deadline = Some(get_time_from_string(x));
+            if args.due_today_count {
+                println!("{}", count_due_today(&task_list, Local::now()));
+                return;
             }
 
-            let mut start_time: Option<DateTime<Local>> = None;
+            let reopened = reopen_due_tasks(&mut task_list, Local::now());
 
-            if let Some(x) = args.start_time {
-                start_time = Some(get_time_from_string(x));
-            }
+            if !reopened.is_empty() {
+                save_task_list(&meta_path, &task_list, &config);
 
-            if let Some(x) = args.parent_id {
-                let mut found = false;
-                for i in 0..task_list.tasks.len() {
-                    if task_list.tasks[i].id == x {
-                        found = true;
-                        break;
+                if !args.json && !args.plain && !args.compact {
+                    for name in reopened.iter() {
+                        println!("Reopened task '{name}'");
                     }
                 }
-
-                if !found {
-                    println!("Invalid parent id!");
-                    exit(1);
-                }
             }
 
-            let mut new_vec: Vec<String> = vec![];
-
-            if let Some(res) = args.resources {
-                let old_vec: Vec<&str> = res.split(",").collect();
-
-                for s in old_vec {
-                    let new_str = s.trim().to_string();
-
-                    if new_str != "" {
-                        new_vec.push(new_str);
+            if let Some(age) = &config.auto_archive_after {
+                let max_age = match parse_duration(age) {
+                    Ok(x) => x,
+                    Err(e) => e.exit(),
+                };
+
+                let count = match archive_completed_tasks(
+                    &meta_path,
+                    &mut task_list,
+                    max_age,
+                    Local::now(),
+                ) {
+                    Ok(x) => x,
+                    Err(e) => e.exit(),
+                };
+
+                if count > 0 {
+                    save_task_list(&meta_path, &task_list, &config);
+
+                    if !args.json && !args.plain && !args.compact {
+                        println!("Archived {count} tasks completed more than {age} ago");
                     }
                 }
             }
 
-            let new_task = Task {
-                name: args.taskname.clone(),
-                points: args.points,
-                id: id,
-                complete: false,
-                due_date: deadline,
-                start_time: start_time,
-                parent: args.parent_id,
-                resources: new_vec,
-            };
+            let since = args.since.map(get_time_from_string);
+
+            if args.completed {
+                task_list = filter_tasks(&task_list, |x| {
+                    x.complete && since.is_none_or(|s| x.completed_at.is_some_and(|at| at >= s))
+                });
+            }
 
-            task_list.tasks.push(new_task);
+            if args.only_with_due || args.only_no_due {
+                task_list =
+                    filter_tasks(&task_list, |x| x.due_date.is_some() == args.only_with_due);
+            }
 
-            println!("Added task '{}'", args.taskname);
+            if let Some(x) = &args.min_priority {
+                let min = match parse_priority(x) {
+                    Ok(x) => x,
+                    Err(e) => e.exit(),
+                };
 
-            if let Some(x) = args.parent_id {
-                println!("Fitting parent size to children");
+                task_list = filter_tasks(&task_list, |x| x.priority >= min);
+            }
 
-                let mut actual_id = x;
+            if args.min_id.is_some() || args.max_id.is_some() {
+                let min = args.min_id.unwrap_or(0);
+                let max = args.max_id.unwrap_or(usize::MAX);
 
-                loop {
-                    if task_list.tasks[actual_id].parent == None {
-                        break;
-                    }
-                    actual_id = task_list.tasks[actual_id].parent.unwrap();
+                if min > max {
+                    PlannerError::Generic(format!("--min-id {min} is above --max-id {max}")).exit();
                 }
 
-                fit_task_size_to_children(&mut task_list, actual_id);
+                task_list = filter_tasks(&task_list, |x| x.id >= min && x.id <= max);
             }
 
-            fs::write(meta_path, serde_json::to_string(&task_list).unwrap())
-                .expect("Could not write to file");
-        }
-        Commands::Rm(args) => {
-            let mut task_list = get_task_list();
+            let mut page_note: Option<String> = None;
 
-            for i in 0..task_list.tasks.len() {
-                if let Some(x) = task_list.tasks[i].parent {
-                    if x == args.task_id {
-                        task_list.tasks[i].parent = None;
-                    }
+            if args.page.is_some() || args.page_size.is_some() || args.page_delta.is_some() {
+                let mut state = read_list_state(&meta_path);
+
+                if let Some(x) = args.page_size {
+                    state.page_size = x as usize;
                 }
-            }
 
-            let mut name: String = "".to_string();
+                let pages;
+                (task_list, pages) = page_tasks(
+                    &task_list,
+                    &mut state,
+                    args.page.map(|x| x as usize),
+                    args.page_delta,
+                );
 
-            for i in 0..task_list.tasks.len() {
-                if task_list.tasks[i].id == args.task_id {
-                    name = task_list.tasks[i].name.clone();
-                    task_list.tasks.remove(i);
-                    break;
+                if let Err(e) = write_list_state(&meta_path, &state) {
+                    println!("Warning: could not remember the page: {e}");
                 }
+
+                page_note = Some(format!("Page {} of {pages}", state.page));
             }
 
-            if name == "" {
-                println!("Task not found");
+            if args.json {
+                let opts = JsonOptions {
+                    bare: args.bare || args.json_shape == JsonShape::Array,
+                    pretty: args.pretty,
+                    computed_grace: args
+                        .include_computed
+                        .then(|| overdue_threshold(args.overdue_threshold.as_ref(), &config)),
+                    timestamp: args.generated_at,
+                };
+                let list_name = meta_path.file_stem().unwrap().to_string_lossy();
+
+                println!("{}", list_json(&task_list, &list_name, &opts, Local::now()));
                 return;
             }
 
-            fs::write(meta_path, serde_json::to_string(&task_list).unwrap())
-                .expect("Could not write to file");
-
-            println!("Removed task '{name}'")
-        }
-        Commands::Check(args) => {
-            let mut task_list = get_task_list();
-
-            let children = get_all_children_of_task(&task_list, args.task_id);
-
-            let mut cancomplete = true;
+            if args.flat_ids {
+                let order = displayed_order(&task_list);
+                remap_ids(&mut task_list, &order, 1);
+            }
 
-            for i in 0..children.len() {
-                if !children[i].complete {
-                    cancomplete = false;
-                    break;
+            if let Some(field) = args.field {
+                for task in task_list.tasks.iter() {
+                    println!("{}", task_field(task, field, &date_format));
                 }
+                return;
             }
 
-            if !cancomplete {
-                println!("Cannot complete task, complete subtasks before!");
-                exit(1);
+            if args.as_markdown {
+                print!(
+                    "{}",
+                    render_markdown(&task_list, args.markdown_details, &date_format)
+                );
+                return;
             }
 
-            let mut name: String = "".to_string();
+            if !args.columns.is_empty() {
+                print!(
+                    "{}",
+                    render_columns(
+                        &task_list,
+                        &args.columns,
+                        &date_format,
+                        args.plain,
+                        args.truncate_names,
+                    )
+                );
+                return;
+            }
 
-            for i in 0..task_list.tasks.len() {
-                if task_list.tasks[i].id == args.task_id {
-                    name = task_list.tasks[i].name.clone();
-                    task_list.tasks[i].complete = true;
-                    break;
-                }
+            if let Some(by) = args.count_by {
+                print!("{}", render_histogram(&count_tasks_by(&task_list, by)));
+                return;
             }
 
-            if name == "" {
-                println!("Task not found");
+            if args.compact {
+                println!(
+                    "{}",
+                    compact_line(
+                        &task_list,
+                        Local::now(),
+                        overdue_threshold(args.overdue_threshold.as_ref(), &config),
+                        scheme,
+                        args.width_for.unwrap_or(terminal_width())
+                    )
+                );
                 return;
             }
 
-            fs::write(meta_path, serde_json::to_string(&task_list).unwrap())
-                .expect("Could not write to file");
+            if !task_list.tasks.is_empty() {
+                let mut out = String::new();
 
-            println!("Checked off task '{name}'")
-        }
-        Commands::List => {
-            let task_list = get_task_list();
+                if !args.plain {
+                    out += "Tasks:\n";
+                }
+
+                let summary = summarize_points(
+                    &task_list,
+                    Local::now(),
+                    overdue_threshold(args.overdue_threshold.as_ref(), &config),
+                    args.weight_progress,
+                );
+
+                if scheme != ColorScheme::Off && scheme != ColorScheme::Mono {
+                    for (tag, name) in config.tag_colors.iter() {
+                        if color_code(name).is_none() {
+                            println!("Warning: unknown color '{name}' for tag '{tag}'");
+                        }
+                    }
+                }
 
-            if task_list.tasks.len() > 0 {
-                println!("Tasks:");
+                let opts = DisplayOptions {
+                    urgency: args.urgency || args.sort_urgency,
+                    plain: args.plain,
+                    scheme,
+                    relative_dates: args.relative_dates
+                        || (!args.absolute_dates && config.date_display == DateDisplay::Relative),
+                    date_format: date_format.clone(),
+                    width: args.width_for,
+                    name_width: args.truncate_names,
+                    tag_colors: resolve_tag_colors(&config, scheme),
+                    color_lines: config.color_tagged_lines,
+                    completed_since: since,
+                };
+
+                let depth = if args.plain { 0 } else { 1 };
+
+                let render_tasks = |tasks: &TaskList, out: &mut String| {
+                    if args.tree {
+                        render_dependency_forest(tasks, depth, &opts, out);
+                        return;
+                    }
 
-                let mut totpoints = 0;
-                let mut allpoints = 0;
+                    if !args.sort_urgency && args.sort.is_none() && args.around.is_none() {
+                        let tree = generate_task_tree(tasks);
 
-                for i in 0..task_list.tasks.len() {
-                    allpoints += task_list.tasks[i].points;
-                    if task_list.tasks[i].complete {
-                        totpoints += task_list.tasks[i].points;
+                        render_task_tree(tasks, tree, depth, 0, &opts, out);
+                        return;
                     }
-                }
 
-                let tree = generate_task_tree(&task_list);
+                    let now = Local::now();
+                    let mut sorted = tasks.tasks.clone();
+
+                    if args.sort_urgency {
+                        sorted.sort_by(|a, b| {
+                            urgency_score(b, now).total_cmp(&urgency_score(a, now))
+                        });
+                    } else if let Some(key) = args.sort {
+                        sort_tasks(&mut sorted, key, args.sort_secondary, now);
+                    } else {
+                        sorted = displayed_order(tasks)
+                            .iter()
+                            .map(|x| tasks.tasks[get_task_index(tasks, *x).unwrap()].clone())
+                            .collect();
+                    }
+
+                    let mut start = 0;
+                    let mut end = sorted.len();
+
+                    if let Some(id) = args.around {
+                        let Some(pos) = sorted.iter().position(|x| x.id == id) else {
+                            PlannerError::TaskNotFound(id).exit();
+                        };
+
+                        start = pos.saturating_sub(args.context);
+                        end = (pos + args.context + 1).min(sorted.len());
+                    }
+
+                    for task in sorted[start..end].iter() {
+                        if Some(task.id) != args.around {
+                            render_task(task, depth, &opts, out);
+                            continue;
+                        }
+
+                        let mut line = String::new();
+                        render_task(task, depth, &opts, &mut line);
+
+                        if depth > 0 {
+                            line.replace_range(0..2, "> ");
+                        } else {
+                            line.insert_str(0, "> ");
+                        }
+
+                        *out += line.as_str();
+                    }
+                };
+
+                if args.group_complete {
+                    let open = filter_tasks(&task_list, |x| !x.complete);
+                    let done = filter_tasks(&task_list, |x| x.complete);
 
-                print_task_tree(&task_list, tree, 1, 0);
+                    render_tasks(&open, &mut out);
+                    if !args.plain {
+                        out += format!("  Open points: {}\n", summary.open).as_str();
+                    }
 
-                let perc = ((totpoints as f32) / (allpoints as f32) * 100.0) as u32;
+                    if !done.tasks.is_empty() {
+                        if !args.plain {
+                            out += "Completed:\n";
+                        }
 
-                if perc == 0 {
-                    println!("\x1b[31mTotal points: {totpoints} ({}%)\x1b[0m", perc)
-                } else if perc == 100 {
-                    println!("\x1b[32mTotal points: {totpoints} ({}%)\x1b[0m", perc)
+                        render_tasks(&done, &mut out);
+                        if !args.plain {
+                            let earned: u64 = done.tasks.iter().map(|x| x.total_points()).sum();
+                            out += format!("  Completed points: {earned}\n").as_str();
+                        }
+                    }
                 } else {
-                    println!("\x1b[33mTotal points: {totpoints} ({}%)\x1b[0m", perc)
+                    render_tasks(&task_list, &mut out);
                 }
-            } else {
+
+                if !args.plain && !args.no_summary {
+                    if args.remaining_only {
+                        out += remaining_summary(&task_list, &config, scheme).as_str();
+                    } else {
+                        let earned = summary.earned - summary.bonus;
+                        let perc = completion_percentage(earned, summary.total);
+                        let msg = format!(
+                            "Total points: {earned} ({perc}%){}",
+                            bonus_suffix(summary.bonus)
+                        );
+
+                        out += colorize(&msg, summary_status(perc, &config), scheme).as_str();
+                    }
+
+                    if args.highlight_overdue_count {
+                        let status = if summary.overdue > 0 {
+                            Status::Bad
+                        } else {
+                            Status::Good
+                        };
+
+                        out += ", ";
+                        out += colorize(&format!("{} overdue", summary.overdue), status, scheme)
+                            .as_str();
+                    }
+
+                    out += "\n";
+
+                    if let Some(x) = budget_warning(&config, summary.open, scheme) {
+                        out += format!("{x}\n").as_str();
+                    }
+                }
+
+                if let Some(x) = &page_note
+                    && !args.plain
+                {
+                    out += format!("{x}\n").as_str();
+                }
+
+                page_output(&out, args.no_pager);
+            } else if !args.plain {
                 println!("No tasks added")
             }
         }
@@ -0,0 +1,1204 @@
+use chrono::TimeZone;
+
+use super::*;
+
+fn at(y: i32, m: u32, d: u32, h: u32) -> DateTime<Local> {
+    Local.with_ymd_and_hms(y, m, d, h, 0, 0).earliest().unwrap()
+}
+
+fn task(id: usize, points: u32) -> Task {
+    Task::new(id, format!("task {id}"), points)
+}
+
+fn due_task(due: DateTime<Local>) -> Task {
+    Task::builder(0, "a".to_string(), 1)
+        .due_date(Some(due))
+        .build()
+}
+
+fn list(tasks: Vec<Task>) -> TaskList {
+    TaskList {
+        version: None,
+        tasks,
+    }
+}
+
+fn temp_meta_path() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("planner-test-{}", Uuid::new_v4()));
+
+    fs::create_dir_all(&dir).unwrap();
+    dir.join("tasks.json")
+}
+
+#[test]
+fn add_task_rejects_a_taken_id() {
+    let mut tasklist = list(vec![task(0, 1)]);
+
+    let err = add_task(&mut tasklist, task(0, 2), &Config::default()).unwrap_err();
+
+    assert!(matches!(err, PlannerError::Generic(_)));
+    assert_eq!(tasklist.tasks.len(), 1);
+}
+
+#[test]
+fn add_task_rejects_a_missing_parent() {
+    let mut tasklist = list(vec![]);
+    let child = Task::builder(1, "child".to_string(), 1)
+        .parent(Some(7))
+        .build();
+
+    let err = add_task(&mut tasklist, child, &Config::default()).unwrap_err();
+
+    assert!(matches!(err, PlannerError::TaskNotFound(7)));
+    assert!(tasklist.tasks.is_empty());
+}
+
+#[test]
+fn add_task_fits_the_root_parent_around_subtasks() {
+    let mut tasklist = list(vec![task(0, 1)]);
+    let child = Task::builder(1, "child".to_string(), 1)
+        .parent(Some(0))
+        .start_time(Some(at(2024, 3, 1, 9)))
+        .due_date(Some(at(2024, 3, 2, 9)))
+        .build();
+
+    add_task(&mut tasklist, child, &Config::default()).unwrap();
+
+    assert_eq!(tasklist.tasks[0].start_time, Some(at(2024, 3, 1, 9)));
+    assert_eq!(tasklist.tasks[0].due_date, Some(at(2024, 3, 2, 9)));
+}
+
+#[test]
+fn remove_tasks_clears_links_to_the_removed_task() {
+    let mut tasklist = list(vec![task(0, 1), task(1, 1)]);
+    tasklist.tasks[1].parent = Some(0);
+
+    let removed = remove_tasks(&mut tasklist, &[0], false).unwrap();
+
+    assert_eq!(removed[0].id, 0);
+    assert_eq!(tasklist.tasks.len(), 1);
+    assert_eq!(tasklist.tasks[0].parent, None);
+}
+
+#[test]
+fn remove_tasks_reports_a_missing_task() {
+    let mut tasklist = list(vec![task(0, 1)]);
+
+    let err = remove_tasks(&mut tasklist, &[0, 3], false).unwrap_err();
+
+    assert!(matches!(err, PlannerError::TaskNotFound(3)));
+    assert_eq!(tasklist.tasks.len(), 1);
+}
+
+#[test]
+fn check_task_completes_and_uncheck_task_reopens() {
+    let mut tasklist = list(vec![task(0, 3)]);
+    let when = at(2024, 3, 1, 12);
+
+    let outcome = check_task(
+        &mut tasklist,
+        0,
+        when,
+        Some("done".to_string()),
+        None,
+        false,
+        &Config::default(),
+    )
+    .unwrap();
+
+    assert!(outcome.next.is_none());
+    assert!(tasklist.tasks[0].complete);
+    assert_eq!(tasklist.tasks[0].completed_at, Some(when));
+
+    uncheck_task(&mut tasklist, 0).unwrap();
+
+    assert!(!tasklist.tasks[0].complete);
+    assert_eq!(tasklist.tasks[0].completed_at, None);
+    assert_eq!(tasklist.tasks[0].completion_note, None);
+}
+
+#[test]
+fn check_task_refuses_open_subtasks() {
+    let mut tasklist = list(vec![task(0, 1), task(1, 1)]);
+    tasklist.tasks[1].parent = Some(0);
+
+    let result = check_task(
+        &mut tasklist,
+        0,
+        at(2024, 3, 1, 12),
+        None,
+        None,
+        false,
+        &Config::default(),
+    );
+
+    assert!(result.is_err());
+    assert!(!tasklist.tasks[0].complete);
+}
+
+#[test]
+fn check_tasks_waits_for_dependencies() {
+    let mut tasklist = list(vec![task(0, 1), task(1, 1), task(2, 1)]);
+    tasklist.tasks[0].depends_on = vec![1];
+    tasklist.tasks[2].parent = Some(1);
+
+    let (count, skipped, _) = check_tasks(
+        &mut tasklist,
+        &[0, 1],
+        at(2024, 3, 1, 12),
+        None,
+        None,
+        &Config::default(),
+    );
+
+    assert_eq!(count, 0);
+    assert_eq!(skipped, vec![0, 1]);
+}
+
+#[test]
+fn check_tasks_cascades_completions() {
+    let config = Config {
+        cascade_complete: true,
+        ..Default::default()
+    };
+    let mut tasklist = list(vec![task(0, 1), task(1, 1), task(2, 1), task(3, 1)]);
+    tasklist.tasks[0].parent = Some(1);
+    tasklist.tasks[2].depends_on = vec![1];
+
+    let (count, skipped, cascaded) = check_tasks(
+        &mut tasklist,
+        &[0, 2],
+        at(2024, 3, 1, 12),
+        None,
+        None,
+        &config,
+    );
+
+    assert_eq!(count, 1);
+    assert!(skipped.is_empty());
+    assert_eq!(cascaded, vec![1, 2]);
+    assert!(tasklist.tasks.iter().take(3).all(|x| x.complete));
+    assert!(!tasklist.tasks[3].complete);
+}
+
+#[test]
+fn read_template_reports_a_missing_file() {
+    let err = read_template("/nonexistent/planner-template.json").unwrap_err();
+
+    assert!(matches!(err, PlannerError::Generic(_)));
+}
+
+#[test]
+fn read_template_reports_invalid_json() {
+    let path = temp_meta_path().with_file_name("template.json");
+    fs::write(&path, "not json").unwrap();
+
+    let err = read_template(path.to_str().unwrap()).unwrap_err();
+
+    assert!(matches!(err, PlannerError::Parse(_)));
+}
+
+#[test]
+fn written_task_list_reads_back() {
+    let path = temp_meta_path();
+    let tasklist = list(vec![task(0, 3), task(1, 5)]);
+
+    let warnings = write_task_list(&path, &tasklist, &Config::default()).unwrap();
+    let read = read_task_list(&path).unwrap();
+
+    assert!(warnings.is_empty());
+    assert_eq!(read.version, Some(SCHEMA_VERSION));
+    assert_eq!(
+        read.tasks.iter().map(|x| x.points).collect::<Vec<_>>(),
+        vec![3, 5]
+    );
+}
+
+#[test]
+fn parse_time_keeps_the_instant_of_an_rfc3339_offset() {
+    let parsed = parse_time("2024-03-01T10:00:00+05:00").unwrap();
+
+    assert_eq!(parsed, Utc.with_ymd_and_hms(2024, 3, 1, 5, 0, 0).unwrap());
+}
+
+#[test]
+fn stored_timestamps_with_an_offset_load_as_the_same_instant() {
+    let raw = r#"{"tasks": [{"name": "a", "points": 1, "id": 0, "complete": false,
+        "due_date": "2024-03-01T10:00:00-07:00", "start_time": null,
+        "parent": null, "resources": []}]}"#;
+
+    let tasklist: TaskList = serde_json::from_str(raw).unwrap();
+
+    assert_eq!(
+        tasklist.tasks[0].due_date.unwrap(),
+        Utc.with_ymd_and_hms(2024, 3, 1, 17, 0, 0).unwrap()
+    );
+}
+
+#[test]
+fn week_starts_on_the_configured_day() {
+    // 2024-03-03 is a Sunday
+    let sunday = at(2024, 3, 3, 0);
+    let monday = at(2024, 3, 4, 0);
+
+    assert_eq!(
+        get_period_start(Period::Week, sunday, WeekStart::Sunday),
+        sunday
+    );
+    assert_eq!(
+        get_period_start(Period::Week, sunday, WeekStart::Monday),
+        at(2024, 2, 26, 0)
+    );
+    assert_eq!(
+        get_period_start(Period::Week, monday, WeekStart::Monday),
+        monday
+    );
+    assert_eq!(
+        get_period_start(Period::Week, monday, WeekStart::Sunday),
+        sunday
+    );
+}
+
+#[test]
+fn points_from_before_the_week_start_are_not_counted() {
+    let mut tasklist = list(vec![task(0, 3), task(1, 5)]);
+    tasklist.tasks[0].complete = true;
+    tasklist.tasks[0].completed_at = Some(at(2024, 3, 4, 0) - TimeDelta::seconds(1));
+    tasklist.tasks[1].complete = true;
+    tasklist.tasks[1].completed_at = Some(at(2024, 3, 4, 0));
+
+    let since = get_period_start(Period::Week, at(2024, 3, 6, 12), WeekStart::Monday);
+
+    assert_eq!(points_earned_since(&tasklist, since), 5);
+}
+
+fn recurring_task(count: u32) -> Task {
+    Task::builder(0, "water plants".to_string(), 1)
+        .due_date(Some(at(2024, 3, 1, 9)))
+        .recurrence(Some(Recurrence {
+            every_seconds: 86400,
+            until: None,
+            remaining: Some(count),
+            next_id: None,
+        }))
+        .build()
+}
+
+fn check(tasklist: &mut TaskList, id: usize, when: DateTime<Local>) -> CheckOutcome {
+    check_task(tasklist, id, when, None, None, false, &Config::default()).unwrap()
+}
+
+#[test]
+fn recurrence_count_makes_that_many_occurrences() {
+    let mut tasklist = list(vec![recurring_task(3)]);
+    let when = at(2024, 3, 1, 12);
+
+    let mut id = 0;
+    while let Some(next) = check(&mut tasklist, id, when).next {
+        id = next.id;
+    }
+
+    assert_eq!(tasklist.tasks.len(), 3);
+    assert!(tasklist.tasks.iter().all(|x| x.complete));
+    assert_eq!(tasklist.tasks[2].due_date, Some(at(2024, 3, 3, 9)));
+}
+
+#[test]
+fn checking_a_recurring_task_again_keeps_one_next_occurrence() {
+    let mut tasklist = list(vec![recurring_task(5)]);
+    let when = at(2024, 3, 1, 12);
+
+    assert!(check(&mut tasklist, 0, when).next.is_some());
+    uncheck_task(&mut tasklist, 0).unwrap();
+    assert!(check(&mut tasklist, 0, when).next.is_none());
+
+    assert_eq!(tasklist.tasks.len(), 2);
+}
+
+#[test]
+fn out_of_range_recurrence_is_a_parse_error() {
+    let mut task = recurring_task(3);
+    task.recurrence.as_mut().unwrap().every_seconds = i64::MAX;
+
+    let err = next_occurrence(&task, 1, at(2024, 3, 1, 12)).unwrap_err();
+    assert!(matches!(err, PlannerError::Parse(_)));
+
+    // A valid duration that still moves the due date past what a date can hold
+    task.recurrence.as_mut().unwrap().every_seconds = 1_000_000_000_000_000;
+
+    let err = next_occurrence(&task, 1, at(2024, 3, 1, 12)).unwrap_err();
+    assert!(matches!(err, PlannerError::Parse(_)));
+}
+
+#[test]
+fn out_of_range_recurrence_still_renders() {
+    let mut task = recurring_task(3);
+    task.recurrence.as_mut().unwrap().every_seconds = i64::MAX;
+
+    let opts = DisplayOptions {
+        urgency: false,
+        plain: true,
+        scheme: ColorScheme::Default,
+        relative_dates: false,
+        date_format: "%Y-%m-%d".to_string(),
+        width: None,
+        name_width: None,
+        tag_colors: BTreeMap::new(),
+        color_lines: false,
+        completed_since: None,
+    };
+    let mut out = String::new();
+
+    render_task(&task, 0, &opts, &mut out);
+
+    assert!(out.contains(&format!("Repeats every {} seconds", i64::MAX)));
+}
+
+#[test]
+fn invalid_date_formats_are_rejected() {
+    assert!(validate_date_format("%Y-%m-%d %H:%M").is_ok());
+    assert!(matches!(
+        validate_date_format("%Q"),
+        Err(PlannerError::Parse(_))
+    ));
+
+    let config = Config {
+        date_format: Some("%Y-%".to_string()),
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        validate_config(&config),
+        Err(PlannerError::Parse(_))
+    ));
+}
+
+#[test]
+fn full_progress_loads_as_complete() {
+    let mut task = task(0, 1);
+    task.progress = Some(100);
+
+    let raw = serde_json::to_string(&task).unwrap();
+    let read: Task = serde_json::from_str(&raw).unwrap();
+
+    assert!(read.complete);
+
+    task.progress = Some(99);
+
+    let raw = serde_json::to_string(&task).unwrap();
+    let read: Task = serde_json::from_str(&raw).unwrap();
+
+    assert!(!read.complete);
+}
+
+#[test]
+fn uncheck_clears_progress() {
+    let mut tasklist = list(vec![task(0, 1)]);
+
+    set_progress(&mut tasklist, 0, 100, at(2024, 3, 1, 12)).unwrap();
+    assert!(tasklist.tasks[0].complete);
+
+    uncheck_task(&mut tasklist, 0).unwrap();
+
+    let raw = serde_json::to_string(&tasklist).unwrap();
+    let read: TaskList = serde_json::from_str(&raw).unwrap();
+
+    assert_eq!(read.tasks[0].progress, None);
+    assert!(!read.tasks[0].complete);
+}
+
+#[test]
+fn partial_progress_reopens_a_checked_off_task() {
+    let mut tasklist = list(vec![task(0, 1)]);
+
+    check(&mut tasklist, 0, at(2024, 3, 1, 12));
+    set_progress(&mut tasklist, 0, 40, at(2024, 3, 1, 13)).unwrap();
+
+    assert!(!tasklist.tasks[0].complete);
+    assert_eq!(tasklist.tasks[0].progress, Some(40));
+}
+
+#[test]
+fn jsonl_export_reads_back_the_same_tasks() {
+    let mut tasklist = list(vec![
+        Task::builder(0, "plan".to_string(), 3)
+            .due_date(Some(at(2024, 3, 1, 9)))
+            .tags(vec!["work".to_string()])
+            .reminders(vec![TimeDelta::hours(1)])
+            .build(),
+        Task::builder(1, "do".to_string(), 5)
+            .parent(Some(0))
+            .depends_on(vec![0])
+            .build(),
+    ]);
+    tasklist.tasks[0].complete = true;
+    tasklist.tasks[0].completed_at = Some(at(2024, 3, 1, 8));
+
+    let raw = generate_jsonl(&tasklist);
+    let read = parse_jsonl_tasks(&raw).unwrap();
+
+    assert_eq!(raw.lines().count(), 2);
+    assert_eq!(
+        serde_json::to_string(&read).unwrap(),
+        serde_json::to_string(&tasklist.tasks).unwrap()
+    );
+}
+
+#[test]
+fn jsonl_errors_name_the_line() {
+    let raw = format!(
+        "{}\n\nnot json\n",
+        serde_json::to_string(&task(0, 1)).unwrap()
+    );
+
+    let err = parse_jsonl_tasks(&raw).unwrap_err();
+
+    assert!(err.to_string().contains("line 3"));
+}
+
+#[test]
+fn stats_json_has_the_documented_fields() {
+    let mut tasklist = list(vec![
+        task(0, 3),
+        Task::builder(1, "late".to_string(), 5)
+            .due_date(Some(at(2024, 3, 1, 9)))
+            .tags(vec!["work".to_string()])
+            .build(),
+    ]);
+    tasklist.tasks[0].complete = true;
+
+    let stats = compute_stats(&tasklist, at(2024, 3, 2, 9), TimeDelta::zero(), true);
+    let json = serde_json::to_value(&stats).unwrap();
+
+    assert_eq!(json["tasks"], 2);
+    assert_eq!(json["completed"], 1);
+    assert_eq!(json["points_earned"], 3);
+    assert_eq!(json["points_total"], 8);
+    assert_eq!(json["percent"], 37);
+    assert_eq!(json["overdue"], 1);
+    assert!(json["next_due"].is_string());
+    assert_eq!(json["by_tag"]["work"]["total"], 5);
+    assert_eq!(json["by_tag"]["(untagged)"]["earned"], 3);
+
+    let json = serde_json::to_value(compute_stats(
+        &tasklist,
+        at(2024, 3, 2, 9),
+        TimeDelta::zero(),
+        false,
+    ))
+    .unwrap();
+
+    assert!(json.get("by_tag").is_none());
+}
+
+#[test]
+fn jsonl_tasks_without_dates_import() {
+    let raw = r#"{"name": "a", "points": 1, "id": 0, "complete": false, "parent": null, "resources": []}"#;
+
+    let tasks = parse_jsonl_tasks(raw).unwrap();
+
+    assert_eq!(tasks[0].due_date, None);
+    assert_eq!(tasks[0].start_time, None);
+}
+
+#[test]
+fn a_write_that_does_not_read_back_restores_the_backup() {
+    let path = temp_meta_path();
+    let config = Config::default();
+
+    write_task_list(&path, &list(vec![task(0, 3)]), &config).unwrap();
+    write_task_list(&path, &list(vec![task(0, 3), task(1, 5)]), &config).unwrap();
+    let before = fs::read_to_string(get_backup_path(&path)).unwrap();
+
+    assert!(write_verified(&path, "{\"tasks\": [", true).is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), before);
+}
+
+#[test]
+fn a_first_write_that_does_not_read_back_is_removed() {
+    let path = temp_meta_path();
+
+    assert!(write_verified(&path, "{\"tasks\": [", false).is_err());
+    assert!(!path.exists());
+}
+
+#[test]
+fn a_task_due_right_now_is_not_overdue_yet() {
+    let now = at(2024, 3, 1, 12);
+
+    assert!(!due_task(now).is_overdue(now));
+    assert!(due_task(now - TimeDelta::seconds(1)).is_overdue(now));
+    assert!(!due_task(now - TimeDelta::hours(1)).is_overdue_after(now, TimeDelta::hours(1)));
+    assert!(
+        due_task(now - TimeDelta::hours(1))
+            .is_overdue_after(now + TimeDelta::seconds(1), TimeDelta::hours(1))
+    );
+}
+
+#[test]
+fn due_within_counts_now_but_not_the_end_of_the_window() {
+    let now = at(2024, 3, 1, 12);
+    let window = TimeDelta::hours(2);
+
+    assert!(due_task(now).is_due_within(now, window));
+    assert!(due_task(now - TimeDelta::days(1)).is_due_within(now, window));
+    assert!(due_task(now + window - TimeDelta::seconds(1)).is_due_within(now, window));
+    assert!(!due_task(now + window).is_due_within(now, window));
+}
+
+fn named(id: usize, name: &str, points: u32) -> Task {
+    Task::new(id, name.to_string(), points)
+}
+
+#[test]
+fn merge_skip_keeps_the_existing_task() {
+    let mut tasklist = list(vec![named(0, "a", 1)]);
+
+    let report = merge_tasks(
+        &mut tasklist,
+        vec![named(0, "a", 5), named(1, "b", 2)],
+        MergePolicy::Skip,
+    );
+
+    assert_eq!(tasklist.tasks.len(), 2);
+    assert_eq!(tasklist.tasks[0].points, 1);
+    assert_eq!(report.len(), 2);
+}
+
+#[test]
+fn paging_with_a_zero_page_size_shows_one_task_a_page() {
+    let tasklist = list(vec![task(0, 1), task(1, 1), task(2, 1)]);
+    let mut state: ListState = serde_json::from_str(r#"{"page": 2, "page_size": 0}"#).unwrap();
+
+    let (shown, pages) = page_tasks(&tasklist, &mut state, None, None);
+
+    assert_eq!(pages, 3);
+    assert_eq!(state.page_size, 1);
+    assert_eq!(shown.tasks.len(), 1);
+    assert_eq!(shown.tasks[0].id, 1);
+}
+
+#[test]
+fn merge_replace_keeps_the_id_and_takes_the_new_fields() {
+    let mut tasklist = list(vec![named(3, "a", 1)]);
+
+    merge_tasks(
+        &mut tasklist,
+        vec![named(0, "A", 5), named(1, "b", 2)],
+        MergePolicy::Replace,
+    );
+
+    assert_eq!(tasklist.tasks.len(), 2);
+    assert_eq!(tasklist.tasks[0].id, 3);
+    assert_eq!(tasklist.tasks[0].points, 5);
+    assert_eq!(tasklist.tasks[1].id, 4);
+}
+
+#[test]
+fn merge_append_adds_every_task() {
+    let mut tasklist = list(vec![named(0, "a", 1)]);
+
+    merge_tasks(
+        &mut tasklist,
+        vec![named(0, "a", 5), named(1, "b", 2)],
+        MergePolicy::Append,
+    );
+
+    assert_eq!(tasklist.tasks.len(), 3);
+    assert_eq!(
+        tasklist.tasks.iter().map(|x| x.id).collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+}
+
+fn json_options() -> JsonOptions {
+    JsonOptions {
+        bare: false,
+        pretty: false,
+        computed_grace: None,
+        timestamp: false,
+    }
+}
+
+#[test]
+fn list_json_sorts_tasks_by_id() {
+    let tasklist = list(vec![task(2, 1), task(0, 1), task(1, 1)]);
+
+    let json = list_json(&tasklist, "tasks", &json_options(), at(2024, 3, 1, 12));
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let ids: Vec<u64> = value["tasks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|x| x["id"].as_u64().unwrap())
+        .collect();
+
+    assert_eq!(ids, vec![0, 1, 2]);
+}
+
+#[test]
+fn list_json_is_the_same_between_runs_unless_timestamped() {
+    let tasklist = list(vec![task(0, 1)]);
+    let mut opts = json_options();
+
+    let first = list_json(&tasklist, "tasks", &opts, at(2024, 3, 1, 12));
+    let second = list_json(&tasklist, "tasks", &opts, at(2024, 3, 2, 12));
+
+    assert_eq!(first, second);
+    assert!(!first.contains("generated_at"));
+
+    opts.timestamp = true;
+    let value: serde_json::Value =
+        serde_json::from_str(&list_json(&tasklist, "tasks", &opts, at(2024, 3, 1, 12))).unwrap();
+
+    assert!(value["generated_at"].is_string());
+}
+
+#[test]
+fn point_totals_do_not_overflow_near_u32_max() {
+    let mut tasklist = list(vec![
+        task(0, u32::MAX),
+        task(1, u32::MAX),
+        task(2, u32::MAX - 1),
+        task(3, u32::MAX),
+    ]);
+    tasklist.tasks[0].complete = true;
+    tasklist.tasks[1].complete = true;
+    tasklist.tasks[2].progress = Some(50);
+
+    let max = u32::MAX as u64;
+    let summary = summarize_points(&tasklist, at(2024, 3, 1, 12), TimeDelta::zero(), true);
+
+    assert_eq!(summary.total, 4 * max - 1);
+    assert_eq!(summary.open, 2 * max - 1);
+    assert_eq!(summary.earned, 2 * max + (max - 1) / 2);
+    assert_eq!(completion_percentage(summary.earned, summary.total), 62);
+
+    let stats = compute_stats(&tasklist, at(2024, 3, 1, 12), TimeDelta::zero(), false);
+
+    assert_eq!(stats.points_total, 4 * max - 1);
+    assert_eq!(stats.points_earned, 2 * max);
+    assert_eq!(stats.percent, 50);
+
+    assert_eq!(
+        remaining_points(&tasklist),
+        (max + (max - 1) / 2, 2 * max - 1)
+    );
+}
+
+#[test]
+fn reopening_a_recurring_task_drops_its_open_next_occurrence() {
+    let mut tasklist = list(vec![recurring_task(5)]);
+    let when = at(2024, 3, 1, 12);
+
+    check_task(
+        &mut tasklist,
+        0,
+        when,
+        None,
+        Some(when + TimeDelta::hours(1)),
+        false,
+        &Config::default(),
+    )
+    .unwrap();
+    assert_eq!(tasklist.tasks.len(), 2);
+
+    let reopened = reopen_due_tasks(&mut tasklist, when + TimeDelta::hours(2));
+
+    assert_eq!(reopened, vec!["water plants".to_string()]);
+    assert_eq!(tasklist.tasks.len(), 1);
+    assert!(!tasklist.tasks[0].complete);
+
+    // Checking it off again makes the next occurrence anew
+    assert!(check(&mut tasklist, 0, when).next.is_some());
+    assert_eq!(tasklist.tasks.len(), 2);
+}
+
+#[test]
+fn reopening_keeps_a_next_occurrence_already_completed() {
+    let mut tasklist = list(vec![recurring_task(5)]);
+    let when = at(2024, 3, 1, 12);
+
+    check_task(
+        &mut tasklist,
+        0,
+        when,
+        None,
+        Some(when + TimeDelta::hours(1)),
+        false,
+        &Config::default(),
+    )
+    .unwrap();
+    check(&mut tasklist, 1, when);
+
+    reopen_due_tasks(&mut tasklist, when + TimeDelta::hours(2));
+
+    assert!(!tasklist.tasks[0].complete);
+    assert!(tasklist.tasks[1].complete);
+    assert_eq!(tasklist.tasks.len(), 3);
+}
+
+#[test]
+fn secondary_sort_key_breaks_ties() {
+    let mut tasks = vec![named(0, "c", 5), named(1, "a", 3), named(2, "b", 5)];
+
+    sort_tasks(&mut tasks, SortKey::Points, None, at(2024, 3, 1, 12));
+    assert_eq!(
+        tasks.iter().map(|x| x.id).collect::<Vec<_>>(),
+        vec![0, 2, 1]
+    );
+
+    sort_tasks(
+        &mut tasks,
+        SortKey::Points,
+        Some(SortKey::Name),
+        at(2024, 3, 1, 12),
+    );
+    assert_eq!(
+        tasks.iter().map(|x| x.id).collect::<Vec<_>>(),
+        vec![2, 0, 1]
+    );
+}
+
+fn tagged(id: usize, tags: &[&str]) -> Task {
+    Task::builder(id, format!("task {id}"), 1)
+        .tags(tags.iter().map(|x| x.to_string()).collect())
+        .build()
+}
+
+#[test]
+fn renaming_a_tag_to_itself_keeps_it() {
+    let mut tasklist = list(vec![tagged(0, &["work"]), tagged(1, &[])]);
+
+    assert_eq!(rename_tag(&mut tasklist, "work", "work"), 1);
+    assert_eq!(tasklist.tasks[0].tags, vec!["work".to_string()]);
+}
+
+#[test]
+fn renaming_onto_an_existing_tag_keeps_one() {
+    let mut tasklist = list(vec![
+        tagged(0, &["home", "work", "urgent"]),
+        tagged(1, &["work"]),
+    ]);
+
+    assert_eq!(rename_tag(&mut tasklist, "work", "home"), 2);
+    assert_eq!(
+        tasklist.tasks[0].tags,
+        vec!["home".to_string(), "urgent".to_string()]
+    );
+    assert_eq!(tasklist.tasks[1].tags, vec!["home".to_string()]);
+}
+
+#[test]
+fn task_list_schema_is_valid_json() {
+    let schema: serde_json::Value = serde_json::from_str(&task_list_schema()).unwrap();
+
+    assert_eq!(schema["title"], "TaskList");
+    assert!(schema["$schema"].is_string());
+    assert!(schema["$defs"]["Task"]["properties"]["points"].is_object());
+}
+
+#[test]
+fn overdue_grace_applies_to_stats_report_and_html() {
+    let now = at(2024, 3, 1, 12);
+    let tasklist = list(vec![due_task(now - TimeDelta::minutes(30))]);
+    let grace = TimeDelta::hours(1);
+    let summary = summarize_points(&tasklist, now, grace, false);
+
+    assert_eq!(summary.overdue, 0);
+    assert_eq!(compute_stats(&tasklist, now, grace, false).overdue, 0);
+    assert_eq!(
+        compute_stats(&tasklist, now, TimeDelta::zero(), false).overdue,
+        1
+    );
+
+    let report = generate_report(
+        &tasklist,
+        Period::Day,
+        now,
+        grace,
+        WeekStart::Monday,
+        "%Y-%m-%d",
+    );
+    assert!(report.contains("## Overdue (0)"));
+
+    assert_eq!(
+        task_status(&tasklist.tasks[0], now, grace),
+        Some(Status::Warning)
+    );
+    assert_eq!(
+        task_status(&tasklist.tasks[0], now, TimeDelta::zero()),
+        Some(Status::Bad)
+    );
+}
+
+#[test]
+fn generic_error_json() {
+    assert_eq!(
+        PlannerError::Generic("boom".to_string()).to_json(),
+        serde_json::json!({"error": "error", "message": "boom"})
+    );
+}
+
+#[test]
+fn not_initialized_error_json() {
+    let json = PlannerError::NotInitialized.to_json();
+
+    assert_eq!(json["error"], "not_initialized");
+    assert!(json["message"].is_string());
+    assert_eq!(json.as_object().unwrap().len(), 2);
+}
+
+#[test]
+fn task_not_found_error_json_has_the_id() {
+    assert_eq!(
+        PlannerError::TaskNotFound(7).to_json(),
+        serde_json::json!({"error": "task_not_found", "message": "Task #7 not found", "id": 7})
+    );
+}
+
+#[test]
+fn parse_error_json() {
+    assert_eq!(
+        PlannerError::Parse("bad date".to_string()).to_json(),
+        serde_json::json!({"error": "parse_error", "message": "bad date"})
+    );
+}
+
+fn done_at(id: usize, when: DateTime<Local>) -> Task {
+    let mut task = task(id, 1);
+    task.complete = true;
+    task.completed_at = Some(when);
+    task
+}
+
+#[test]
+fn streak_counts_days_either_side_of_midnight() {
+    let midnight = Local.with_ymd_and_hms(2024, 3, 2, 0, 0, 0).unwrap();
+    let tasklist = list(vec![
+        done_at(0, midnight - TimeDelta::seconds(1)),
+        done_at(1, midnight),
+    ]);
+
+    assert_eq!(completion_streak(&tasklist, midnight), 2);
+    assert_eq!(
+        completion_streak(&tasklist, midnight - TimeDelta::seconds(1)),
+        1
+    );
+}
+
+#[test]
+fn streak_stops_at_a_gap_day() {
+    let tasklist = list(vec![
+        done_at(0, at(2024, 3, 1, 12)),
+        done_at(1, at(2024, 3, 3, 12)),
+        done_at(2, at(2024, 3, 4, 8)),
+    ]);
+
+    assert_eq!(completion_streak(&tasklist, at(2024, 3, 4, 20)), 2);
+    assert_eq!(completion_streak(&tasklist, at(2024, 3, 2, 12)), 0);
+    assert_eq!(completion_streak(&tasklist, at(2024, 3, 5, 12)), 0);
+}
+
+#[test]
+fn streak_runs_across_daylight_saving_changes() {
+    // Around the spring and autumn changes in both the EU and the US, at
+    // times that exist in every time zone
+    for (month, first) in [(3, 9), (3, 29), (10, 26), (11, 2)] {
+        let tasklist = list(vec![
+            done_at(0, at(2024, month, first, 23)),
+            done_at(1, at(2024, month, first + 1, 0) + TimeDelta::minutes(30)),
+            done_at(2, at(2024, month, first + 2, 12)),
+        ]);
+
+        assert_eq!(
+            completion_streak(&tasklist, at(2024, month, first + 2, 23)),
+            3
+        );
+    }
+}
+
+#[test]
+fn period_starts_when_midnight_is_skipped() {
+    // Days whose midnight is skipped in America/Santiago and Asia/Beirut
+    for (month, day) in [(9, 8), (3, 31)] {
+        let now = at(2024, month, day, 12);
+        let start = get_period_start(Period::Day, now, WeekStart::Monday);
+
+        assert_eq!(start.date_naive(), now.date_naive());
+        assert!(start <= now);
+        assert!((start - TimeDelta::seconds(1)).date_naive() < now.date_naive());
+    }
+}
+
+#[test]
+fn streak_bonus_is_awarded_once_per_step() {
+    let config = Config {
+        streak_bonus_days: 2,
+        streak_bonus_points: 4,
+        ..Default::default()
+    };
+    let mut tasklist = list(vec![done_at(0, at(2024, 3, 1, 12)), task(1, 3), task(2, 3)]);
+
+    let (count, _, _) = check_tasks(
+        &mut tasklist,
+        &[1, 2],
+        at(2024, 3, 2, 12),
+        None,
+        None,
+        &config,
+    );
+
+    assert_eq!(count, 2);
+    assert_eq!(tasklist.tasks[1].bonus_points, 4);
+    assert_eq!(tasklist.tasks[2].bonus_points, 0);
+
+    // Checking the awarded task off again keeps its bonus
+    check_task(
+        &mut tasklist,
+        1,
+        at(2024, 3, 2, 13),
+        None,
+        None,
+        false,
+        &config,
+    )
+    .unwrap();
+    assert_eq!(tasklist.tasks[1].bonus_points, 4);
+
+    let summary = summarize_points(&tasklist, at(2024, 3, 2, 14), TimeDelta::zero(), false);
+
+    assert_eq!(summary.total, 7);
+    assert_eq!(summary.earned, 11);
+}
+
+#[test]
+fn stats_report_a_streak_bonus_apart_from_the_percentage() {
+    let config = Config {
+        streak_bonus_days: 1,
+        streak_bonus_points: 5,
+        points_budget: Some(1),
+        ..Default::default()
+    };
+    let mut tasklist = list(vec![task(0, 3), task(1, 2)]);
+
+    check_task(
+        &mut tasklist,
+        0,
+        at(2024, 3, 1, 12),
+        None,
+        None,
+        false,
+        &config,
+    )
+    .unwrap();
+
+    let stats = compute_stats(&tasklist, at(2024, 3, 1, 13), TimeDelta::zero(), true);
+
+    assert_eq!(stats.points_earned, 8);
+    assert_eq!(stats.points_bonus, 5);
+    assert_eq!(stats.points_total, 5);
+    assert_eq!(stats.points_open, 2);
+    assert_eq!(stats.percent, 60);
+    assert_eq!(
+        stats.by_tag.unwrap()["(untagged)"].describe("(untagged)"),
+        "  (untagged): 2 tasks, 1 completed, 3/5 points (60%) + 5 bonus"
+    );
+    assert!(budget_warning(&config, stats.points_open, ColorScheme::Off).is_some());
+
+    let summary = summarize_points(&tasklist, at(2024, 3, 1, 13), TimeDelta::zero(), false);
+
+    assert_eq!(summary.bonus, 5);
+    assert_eq!(summary.open, stats.points_open);
+}
+
+fn penalty_config() -> Config {
+    Config {
+        late_penalty_percent: 50,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn completing_exactly_when_due_is_on_time() {
+    let due = at(2024, 3, 1, 12);
+    let task = Task::builder(0, "a".to_string(), 10)
+        .due_date(Some(due))
+        .build();
+
+    assert_eq!(late_penalty(&task, due, &penalty_config()), 0);
+    assert_eq!(
+        late_penalty(&task, due + TimeDelta::seconds(1), &penalty_config()),
+        5
+    );
+}
+
+#[test]
+fn late_penalty_of_huge_tasks_does_not_overflow() {
+    let task = Task::builder(0, "a".to_string(), u32::MAX)
+        .due_date(Some(at(2024, 3, 1, 12)))
+        .build();
+
+    assert_eq!(
+        late_penalty(&task, at(2024, 3, 2, 12), &penalty_config()),
+        u32::MAX / 2
+    );
+}
+
+#[test]
+fn late_penalty_only_lowers_what_was_earned() {
+    let due = at(2024, 3, 1, 12);
+    let mut tasklist = list(vec![
+        Task::builder(0, "late".to_string(), 10)
+            .due_date(Some(due))
+            .build(),
+        Task::builder(1, "on time".to_string(), 10)
+            .due_date(Some(due))
+            .build(),
+    ]);
+
+    let late = check_task(
+        &mut tasklist,
+        0,
+        due + TimeDelta::seconds(1),
+        None,
+        None,
+        false,
+        &penalty_config(),
+    )
+    .unwrap();
+    let on_time = check_task(&mut tasklist, 1, due, None, None, false, &penalty_config()).unwrap();
+
+    assert_eq!(late.penalty, 5);
+    assert_eq!(on_time.penalty, 0);
+
+    let summary = summarize_points(&tasklist, due, TimeDelta::zero(), false);
+
+    assert_eq!(summary.total, 20);
+    assert_eq!(summary.earned, 15);
+}
+
+#[test]
+fn late_penalty_does_not_count_towards_the_budget() {
+    let due = at(2024, 3, 1, 12);
+    let config = Config {
+        points_budget: Some(4),
+        ..penalty_config()
+    };
+    let mut tasklist = list(vec![
+        Task::builder(0, "late".to_string(), 10)
+            .due_date(Some(due))
+            .build(),
+        task(1, 4),
+    ]);
+
+    check_task(
+        &mut tasklist,
+        0,
+        due + TimeDelta::days(1),
+        None,
+        None,
+        false,
+        &config,
+    )
+    .unwrap();
+
+    let stats = compute_stats(&tasklist, due, TimeDelta::zero(), false);
+
+    assert_eq!(stats.points_total - stats.points_earned, 9);
+    assert_eq!(stats.points_open, 4);
+    assert!(budget_warning(&config, stats.points_open, ColorScheme::Off).is_none());
+}
+
+fn depending(id: usize, deps: &[usize]) -> Task {
+    Task::builder(id, format!("p{id}"), 1)
+        .depends_on(deps.to_vec())
+        .build()
+}
+
+fn close_deps(tasklist: &mut TaskList, id: usize) -> CheckOutcome {
+    check_task(
+        tasklist,
+        id,
+        at(2024, 3, 1, 12),
+        None,
+        None,
+        true,
+        &Config::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn closing_dependencies_of_a_diamond_closes_them_all() {
+    // 2 depends on 0 and 1, and 1 on 0 too
+    let mut tasklist = list(vec![
+        depending(0, &[]),
+        depending(1, &[0]),
+        depending(2, &[0, 1]),
+    ]);
+
+    let outcome = close_deps(&mut tasklist, 2);
+
+    assert_eq!(outcome.closed, vec![0, 1]);
+    assert!(outcome.left_open.is_empty());
+    assert!(tasklist.tasks.iter().all(|x| x.complete));
+}
+
+#[test]
+fn closing_dependencies_in_a_cycle_through_the_task_closes_them() {
+    let mut tasklist = list(vec![
+        depending(0, &[1]),
+        depending(1, &[2]),
+        depending(2, &[0]),
+    ]);
+
+    let outcome = close_deps(&mut tasklist, 0);
+
+    assert_eq!(outcome.closed, vec![2, 1]);
+    assert!(tasklist.tasks.iter().all(|x| x.complete));
+}
+
+#[test]
+fn closing_dependencies_leaves_a_cycle_beyond_the_task_open() {
+    let mut tasklist = list(vec![
+        depending(0, &[1]),
+        depending(1, &[2]),
+        depending(2, &[1]),
+    ]);
+
+    let outcome = close_deps(&mut tasklist, 0);
+
+    assert!(outcome.closed.is_empty());
+    assert_eq!(outcome.left_open, vec![2, 1]);
+    assert!(!tasklist.tasks[1].complete);
+    assert!(!tasklist.tasks[2].complete);
+}
+
+#[test]
+fn removing_a_prerequisite_needs_force() {
+    let mut tasklist = list(vec![
+        depending(0, &[]),
+        depending(1, &[]),
+        depending(2, &[0]),
+    ]);
+
+    let err = remove_tasks(&mut tasklist, &[0, 1], false).unwrap_err();
+
+    assert!(matches!(err, PlannerError::Generic(_)));
+    assert_eq!(tasklist.tasks.len(), 3);
+
+    remove_tasks(&mut tasklist, &[0, 1], true).unwrap();
+
+    assert_eq!(tasklist.tasks.len(), 1);
+    assert!(tasklist.tasks[0].depends_on.is_empty());
+}
+
+#[test]
+fn removing_a_prerequisite_with_its_dependents_needs_no_force() {
+    let mut tasklist = list(vec![depending(0, &[]), depending(1, &[0])]);
+
+    remove_tasks(&mut tasklist, &[0, 1], false).unwrap();
+
+    assert!(tasklist.tasks.is_empty());
+}